@@ -0,0 +1,393 @@
+use crate::color::Rgb;
+use crate::real::Real;
+
+/// Easing used between a keyframe and the next one.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Interpolation<T: Real = f32> {
+    /// Holds the value until the next key.
+    Step,
+    /// Straight blend between the keys.
+    #[default]
+    Linear,
+    /// Eases in and out of each key.
+    Smoothstep,
+    /// CSS style `cubic-bezier(x1, y1, x2, y2)` easing curve; `x1` and `x2` must be in `0..=1`.
+    Bezier {
+        /// Progress of the first control point, in `0..=1`.
+        x1: T,
+        /// Eased value of the first control point.
+        y1: T,
+        /// Progress of the second control point, in `0..=1`.
+        x2: T,
+        /// Eased value of the second control point.
+        y2: T,
+    },
+}
+
+impl<T: Real> Interpolation<T> {
+
+    /// Maps linear progress `t` in `0..=1` to eased progress.
+    pub fn ease(&self, t: T) -> T {
+        let t = t.clamp(T::ZERO, T::ONE);
+        match *self {
+            Interpolation::Step => T::ZERO,
+            Interpolation::Linear => t,
+            Interpolation::Smoothstep => t * t * (T::from_f32(3.0) - T::from_f32(2.0) * t),
+            Interpolation::Bezier { x1, y1, x2, y2 } => {
+                // find the curve parameter whose x is t by bisection (x is monotonic), then return its y
+                let (mut lo, mut hi) = (T::ZERO, T::ONE);
+                for _ in 0..24 {
+                    let mid = T::HALF * (lo + hi);
+                    if cubic_bezier(x1, x2, mid) < t {
+                        lo = mid;
+                    } else {
+                        hi = mid;
+                    }
+                }
+                cubic_bezier(y1, y2, T::HALF * (lo + hi))
+            }
+        }
+    }
+}
+
+//one coordinate of a bezier from 0 to 1 with control points c1 and c2
+
+fn cubic_bezier<T: Real>(c1: T, c2: T, s: T) -> T {
+    let three = T::from_f32(3.0);
+    let r = T::ONE - s;
+    three * r * r * s * c1 + three * r * s * s * c2 + s * s * s
+}
+
+/// Values a [`Track`] can interpolate.
+pub trait Animatable<T: Real>: Copy {
+    /// Value `t` of the way from `self` to `other`, `t` in `0..=1`.
+    fn lerp(self, other: Self, t: T) -> Self;
+}
+
+impl<T: Real> Animatable<T> for T {
+    fn lerp(self, other: Self, t: T) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl<T: Real> Animatable<T> for (T, T) {
+    fn lerp(self, other: Self, t: T) -> Self {
+        (self.0.lerp(other.0, t), self.1.lerp(other.1, t))
+    }
+}
+
+impl<T: Real> Animatable<T> for Rgb {
+    fn lerp(self, other: Self, t: T) -> Self {
+        let t = t.to_f32();
+        let [r0, g0, b0] = self.to_f32();
+        let [r1, g1, b1] = other.to_f32();
+        Rgb::from_f32(r0 + (r1 - r0) * t, g0 + (g1 - g0) * t, b0 + (b1 - b0) * t)
+    }
+}
+
+/// Value of a [`Track`] at one point in time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Keyframe<V, T: Real = f32> {
+    /// Time of the key, in seconds.
+    pub time: T,
+    /// Value reached at `time`.
+    pub value: V,
+    /// Easing from this key to the next.
+    pub interpolation: Interpolation<T>,
+}
+
+/// Keyframes of one animated property, kept sorted by time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track<V, T: Real = f32> {
+    keys: Vec<Keyframe<V, T>>,
+}
+
+impl<V, T: Real> Default for Track<V, T> {
+    fn default() -> Self {
+        Self { keys: Vec::new() }
+    }
+}
+
+impl<V: Animatable<T>, T: Real> Track<V, T> {
+
+    /// Adds a key, replacing any key at the same time.
+    pub fn insert(&mut self, time: T, value: V, interpolation: Interpolation<T>) {
+        let key = Keyframe { time, value, interpolation };
+        match self.keys.iter().position(|k| k.time >= time) {
+            Some(i) if self.keys[i].time == time => self.keys[i] = key,
+            Some(i) => self.keys.insert(i, key),
+            None => self.keys.push(key),
+        }
+    }
+
+    /// Keys in time order.
+    pub fn keys(&self) -> &[Keyframe<V, T>] {
+        &self.keys
+    }
+
+    /// Removes every key.
+    pub fn clear(&mut self) {
+        self.keys.clear();
+    }
+
+    /// Whether there are no keys, so the property is left alone.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Value at `time`, holding the first and last keys outside their range;
+    /// `None` for an empty track.
+    pub fn sample(&self, time: T) -> Option<V> {
+        self.sample_with(time, V::lerp)
+    }
+
+    fn sample_with(&self, time: T, lerp: impl Fn(V, V, T) -> V) -> Option<V> {
+        let first = self.keys.first()?;
+        if time <= first.time {
+            return Some(first.value);
+        }
+
+        for pair in self.keys.windows(2) {
+            let (a, b) = (&pair[0], &pair[1]);
+            if time < b.time {
+                let t = (time - a.time) / (b.time - a.time);
+                return Some(lerp(a.value, b.value, a.interpolation.ease(t)));
+            }
+        }
+
+        self.keys.last().map(|k| k.value)
+    }
+}
+
+impl<T: Real> Track<T, T> {
+
+    /// Like [`Track::sample`] for angles in radians: turns the short way round between
+    /// keys, so 350° to 10° sweeps 20°, and returns a value in `0..TAU`.
+    pub fn sample_angle(&self, time: T) -> Option<T> {
+        self.sample_with(time, |a, b, t| {
+            let d = b - a;
+            let d = d - T::TAU * ((d + T::PI) / T::TAU).floor();
+            a + d * t
+        })
+        .map(|a| a - T::TAU * (a / T::TAU).floor())
+    }
+}
+
+/// Periodic modulation of an emitter's output.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Pulse<T: Real = f32> {
+    /// Always fully on.
+    #[default]
+    Constant,
+    /// Fully on for `duty` (0..=1) of each period, off otherwise.
+    Square {
+        /// Seconds per cycle.
+        period: T,
+        /// Fraction of each cycle spent on.
+        duty: T,
+    },
+    /// Smoothly between 0 and 1.
+    Sine {
+        /// Seconds per cycle.
+        period: T,
+    },
+}
+
+impl<T: Real> Pulse<T> {
+
+    /// Output factor in `0..=1` at `time`.
+    pub fn factor(&self, time: T) -> T {
+        match *self {
+            Pulse::Constant => T::ONE,
+            Pulse::Square { period, duty } => {
+                if phase(time, period) < duty { T::ONE } else { T::ZERO }
+            }
+            Pulse::Sine { period } => T::HALF - T::HALF * (phase(time, period) * T::TAU).cos(),
+        }
+    }
+
+    /// Index of the pulse cycle `time` falls in; always 0 for [`Pulse::Constant`].
+    pub fn cycle(&self, time: T) -> u64 {
+        match *self {
+            Pulse::Square { period, .. } | Pulse::Sine { period } if period > T::ZERO => {
+                (time / period).floor().to_usize() as u64
+            }
+            _ => 0,
+        }
+    }
+}
+
+//fraction of the way through the current period, in 0..1
+
+fn phase<T: Real>(time: T, period: T) -> T {
+    if period <= T::ZERO {
+        return T::ZERO;
+    }
+    let cycles = time / period;
+    cycles - cycles.floor()
+}
+
+/// On/off windows in emitter time; always on when there are no windows.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct Schedule<T: Real = f32> {
+    /// `(start, end)` intervals during which the emitter is on.
+    pub windows: Vec<(T, T)>,
+    /// Repeats the windows every `period` seconds when set.
+    pub period: Option<T>,
+}
+
+impl<T: Real> Schedule<T> {
+
+    /// Whether the emitter is on at `time`.
+    pub fn is_on(&self, time: T) -> bool {
+        if self.windows.is_empty() {
+            return true;
+        }
+        let time = match self.period {
+            Some(period) if period > T::ZERO => phase(time, period) * period,
+            _ => time,
+        };
+        self.windows.iter().any(|&(start, end)| time >= start && time < end)
+    }
+}
+
+/// Scripted behaviour of an [`crate::Emitter`] over its own clock. Empty tracks leave
+/// the matching property alone, so it can still be edited by hand.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct EmitterAnimation<T: Real = f32> {
+    /// Emitter center.
+    pub position: Track<(T, T), T>,
+    /// Heading, in radians; blended the short way around.
+    pub angle: Track<T, T>,
+    /// Multiplier on the emitter's rates.
+    pub strength: Track<T, T>,
+    /// Dye color; only used by [`crate::ColorMode::Solid`].
+    pub color: Track<Rgb, T>,
+    /// Drives the shape size, see [`crate::EmitterShape::set_radius`].
+    pub radius: Track<T, T>,
+
+    /// When the emitter is on at all.
+    pub schedule: Schedule<T>,
+    /// Modulates the output while on.
+    pub pulse: Pulse<T>,
+
+    /// Restarts the tracks every `loop_length` seconds when set.
+    pub loop_length: Option<T>,
+}
+
+impl<T: Real> EmitterAnimation<T> {
+
+    /// Time to sample the tracks at for emitter time `time`.
+    pub fn track_time(&self, time: T) -> T {
+        match self.loop_length {
+            Some(length) if length > T::ZERO => phase(time, length) * length,
+            _ => time,
+        }
+    }
+
+    /// Output factor in `0..=1` from the schedule and pulse at `time`.
+    pub fn activity(&self, time: T) -> T {
+        if self.schedule.is_on(time) { self.pulse.factor(time) } else { T::ZERO }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn easings_run_from_zero_to_one() {
+        let bezier = Interpolation::Bezier { x1: 0.42, y1: 0.0, x2: 0.58, y2: 1.0 };
+        for ease in [Interpolation::Linear, Interpolation::Smoothstep, bezier] {
+            assert!(close(ease.ease(0.0), 0.0));
+            assert!(close(ease.ease(1.0), 1.0));
+            assert!(close(ease.ease(0.5), 0.5), "{ease:?} is symmetric");
+        }
+        assert_eq!(Interpolation::<f64>::Step.ease(0.99), 0.0);
+        assert!(close(Interpolation::Smoothstep.ease(0.25), 0.15625));
+        // clamped outside 0..=1
+        assert_eq!(Interpolation::<f64>::Linear.ease(2.0), 1.0);
+
+        // control points on the diagonal make the bezier linear
+        let straight = Interpolation::Bezier { x1: 0.25, y1: 0.25, x2: 0.75, y2: 0.75 };
+        assert!((straight.ease(0.3) - 0.3f64).abs() < 1e-4);
+    }
+
+    #[test]
+    fn track_interpolates_and_holds_the_ends() {
+        let mut track = Track::<f64, f64>::default();
+        assert_eq!(track.sample(1.0), None);
+
+        track.insert(2.0, 10.0, Interpolation::Linear);
+        track.insert(0.0, 0.0, Interpolation::Step);
+        track.insert(4.0, 30.0, Interpolation::Linear);
+        track.insert(4.0, 20.0, Interpolation::Linear);
+        assert_eq!(track.keys().iter().map(|k| k.time).collect::<Vec<_>>(), [0.0, 2.0, 4.0]);
+
+        assert_eq!(track.sample(-1.0), Some(0.0));
+        assert_eq!(track.sample(1.9), Some(0.0), "step holds");
+        assert_eq!(track.sample(3.0), Some(15.0));
+        assert_eq!(track.sample(9.0), Some(20.0));
+    }
+
+    #[test]
+    fn angles_take_the_short_way_round() {
+        let mut track = Track::<f64, f64>::default();
+        track.insert(0.0, 350f64.to_radians(), Interpolation::Linear);
+        track.insert(1.0, 10f64.to_radians(), Interpolation::Linear);
+
+        assert!(close(track.sample_angle(0.5).unwrap(), 0.0) || close(track.sample_angle(0.5).unwrap(), std::f64::consts::TAU));
+        assert!(close(track.sample_angle(0.25).unwrap(), 355f64.to_radians()));
+        assert!(close(track.sample_angle(0.75).unwrap(), 5f64.to_radians()));
+        assert!(close(track.sample_angle(2.0).unwrap(), 10f64.to_radians()));
+    }
+
+    #[test]
+    fn pulses() {
+        let square = Pulse::Square { period: 2.0, duty: 0.25 };
+        assert_eq!(square.factor(0.4), 1.0);
+        assert_eq!(square.factor(0.6), 0.0);
+        assert_eq!(square.factor(4.2), 1.0);
+        assert_eq!(square.cycle(4.2), 2);
+
+        let sine = Pulse::Sine { period: 4.0 };
+        assert!(close(sine.factor(0.0), 0.0));
+        assert!(close(sine.factor(2.0), 1.0));
+        assert!(close(sine.factor(1.0), 0.5));
+
+        assert_eq!(Pulse::<f64>::Constant.factor(123.0), 1.0);
+        assert_eq!(Pulse::<f64>::Constant.cycle(123.0), 0);
+    }
+
+    #[test]
+    fn schedules() {
+        let mut schedule = Schedule::<f64>::default();
+        assert!(schedule.is_on(5.0));
+
+        schedule.windows = vec![(1.0, 2.0), (3.0, 3.5)];
+        assert!(!schedule.is_on(0.5));
+        assert!(schedule.is_on(1.0));
+        assert!(!schedule.is_on(2.0));
+        assert!(schedule.is_on(3.2));
+        assert!(!schedule.is_on(11.2));
+
+        schedule.period = Some(5.0);
+        assert!(schedule.is_on(11.2));
+        assert!(schedule.is_on(8.2));
+    }
+
+    #[test]
+    fn animation_loops_and_gates() {
+        let mut anim = EmitterAnimation::<f64> { loop_length: Some(3.0), ..Default::default() };
+        assert!(close(anim.track_time(7.5), 1.5));
+
+        anim.schedule.windows = vec![(0.0, 1.0)];
+        anim.pulse = Pulse::Sine { period: 2.0 };
+        assert!(close(anim.activity(0.5), 0.5));
+        assert_eq!(anim.activity(1.5), 0.0);
+    }
+}
@@ -0,0 +1,97 @@
+/// 8-bit sRGB color used for emitters and rendering.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rgb {
+    /// Red channel.
+    pub r: u8,
+    /// Green channel.
+    pub g: u8,
+    /// Blue channel.
+    pub b: u8,
+}
+
+impl Rgb {
+
+    /// All channels at 0.
+    pub const BLACK: Rgb = Rgb::new(0, 0, 0);
+    /// All channels at 255.
+    pub const WHITE: Rgb = Rgb::new(255, 255, 255);
+
+    /// Color from its three channels.
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Channels scaled to `0.0..=1.0`.
+    pub fn to_f32(self) -> [f32; 3] {
+        [self.r as f32 / 255.0, self.g as f32 / 255.0, self.b as f32 / 255.0]
+    }
+
+    /// Builds a color from channels in `0.0..=1.0`, rounded to the nearest level and
+    /// clamped when outside that range.
+    pub fn from_f32(r: f32, g: f32, b: f32) -> Self {
+        let to_u8 = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        Self::new(to_u8(r), to_u8(g), to_u8(b))
+    }
+
+    /// Builds a color from hue in turns (wrapping), saturation and value in `0.0..=1.0`.
+    pub fn from_hsv(h: f32, s: f32, v: f32) -> Self {
+        let h = (h - h.floor()) * 6.0;
+        let c = v * s;
+        let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+        let m = v - c;
+        Self::from_f32(r + m, g + m, b + m)
+    }
+}
+
+impl From<[u8; 3]> for Rgb {
+    fn from([r, g, b]: [u8; 3]) -> Self {
+        Self::new(r, g, b)
+    }
+}
+
+impl From<Rgb> for [u8; 3] {
+    fn from(c: Rgb) -> Self {
+        [c.r, c.g, c.b]
+    }
+}
+
+#[cfg(feature = "egui")]
+impl From<Rgb> for egui::Color32 {
+    fn from(c: Rgb) -> Self {
+        egui::Color32::from_rgb(c.r, c.g, c.b)
+    }
+}
+
+#[cfg(feature = "egui")]
+impl From<egui::Color32> for Rgb {
+    fn from(c: egui::Color32) -> Self {
+        Self::new(c.r(), c.g(), c.b())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hsv_primaries_and_wrapping() {
+        assert_eq!(Rgb::from_hsv(0.0, 1.0, 1.0), Rgb::new(255, 0, 0));
+        assert_eq!(Rgb::from_hsv(1.0 / 3.0, 1.0, 1.0), Rgb::new(0, 255, 0));
+        assert_eq!(Rgb::from_hsv(2.0 / 3.0, 1.0, 1.0), Rgb::new(0, 0, 255));
+
+        // a full turn and negative hues land on the same colors
+        assert_eq!(Rgb::from_hsv(1.0, 1.0, 1.0), Rgb::new(255, 0, 0));
+        assert_eq!(Rgb::from_hsv(-1.0 / 3.0, 1.0, 1.0), Rgb::new(0, 0, 255));
+
+        assert_eq!(Rgb::from_hsv(0.4, 0.0, 1.0), Rgb::WHITE);
+        assert_eq!(Rgb::from_hsv(0.4, 1.0, 0.0), Rgb::BLACK);
+    }
+}
@@ -0,0 +1,113 @@
+use crate::animation::Animatable;
+use crate::color::Rgb;
+use crate::palette::Gradient;
+use crate::real::Real;
+
+//nine evenly spaced samples of each published map, blended linearly in between
+
+const VIRIDIS: [[u8; 3]; 9] = [
+    [68, 1, 84], [71, 45, 123], [59, 82, 139], [44, 114, 142], [33, 145, 140],
+    [40, 174, 128], [94, 201, 98], [173, 220, 48], [253, 231, 37],
+];
+const MAGMA: [[u8; 3]; 9] = [
+    [0, 0, 4], [28, 16, 68], [79, 18, 123], [129, 37, 129], [181, 54, 122],
+    [229, 80, 100], [251, 136, 97], [254, 194, 135], [252, 253, 191],
+];
+const COOLWARM: [[u8; 3]; 9] = [
+    [59, 76, 192], [98, 130, 234], [141, 176, 254], [184, 208, 249], [221, 221, 221],
+    [245, 196, 173], [244, 154, 123], [222, 96, 77], [180, 4, 38],
+];
+const TURBO: [[u8; 3]; 9] = [
+    [48, 18, 59], [70, 107, 227], [41, 187, 236], [49, 242, 153], [162, 252, 60],
+    [237, 208, 58], [251, 128, 34], [210, 49, 5], [122, 4, 3],
+];
+
+/// Maps `0..=1` to a color. Viridis and magma are perceptually uniform for unsigned
+/// data, coolwarm is diverging for signed data centered on zero, and turbo is a
+/// high-contrast rainbow.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub enum Colormap<T: Real = f32> {
+    /// Dark purple through teal to yellow.
+    #[default]
+    Viridis,
+    /// Black through purple and orange to pale yellow.
+    Magma,
+    /// Blue through light gray to red.
+    Coolwarm,
+    /// Dark blue through green and yellow to dark red.
+    Turbo,
+    /// Black to white.
+    Grayscale,
+    /// A user-defined gradient.
+    Custom(Gradient<T>),
+}
+
+impl<T: Real> Colormap<T> {
+
+    /// Every built-in map, for pickers.
+    pub const BUILT_IN: [Colormap<T>; 5] = [Colormap::Viridis, Colormap::Magma, Colormap::Coolwarm, Colormap::Turbo, Colormap::Grayscale];
+
+    /// Label shown in pickers.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Colormap::Viridis => "Viridis",
+            Colormap::Magma => "Magma",
+            Colormap::Coolwarm => "Coolwarm",
+            Colormap::Turbo => "Turbo",
+            Colormap::Grayscale => "Grayscale",
+            Colormap::Custom(_) => "Custom",
+        }
+    }
+
+    /// Color at `t`, clamped into `0..=1`.
+    pub fn sample(&self, t: T) -> Rgb {
+        let t = t.clamp(T::ZERO, T::ONE);
+        let table = match self {
+            Colormap::Viridis => &VIRIDIS,
+            Colormap::Magma => &MAGMA,
+            Colormap::Coolwarm => &COOLWARM,
+            Colormap::Turbo => &TURBO,
+            Colormap::Grayscale => return Rgb::from_f32(t.to_f32(), t.to_f32(), t.to_f32()),
+            Colormap::Custom(gradient) => return gradient.sample(t),
+        };
+
+        let scaled = t * T::from_usize(table.len() - 1);
+        let i = scaled.floor().to_usize().min(table.len() - 2);
+        let f = scaled - T::from_usize(i);
+        Rgb::from(table[i]).lerp(Rgb::from(table[i + 1]), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_end_on_their_published_colors() {
+        let ends = [
+            (Colormap::Viridis, VIRIDIS[0], VIRIDIS[8]),
+            (Colormap::Magma, MAGMA[0], MAGMA[8]),
+            (Colormap::Coolwarm, COOLWARM[0], COOLWARM[8]),
+            (Colormap::Turbo, TURBO[0], TURBO[8]),
+            (Colormap::Grayscale, [0, 0, 0], [255, 255, 255]),
+        ];
+        for (map, low, high) in ends {
+            assert_eq!(map.sample(0.0f32), Rgb::from(low), "{}", map.name());
+            assert_eq!(map.sample(1.0), Rgb::from(high), "{}", map.name());
+        }
+        assert_eq!(Colormap::<f32>::Viridis.sample(0.0), Rgb::new(68, 1, 84));
+        assert_eq!(Colormap::<f32>::Viridis.sample(1.0), Rgb::new(253, 231, 37));
+        assert_eq!(Colormap::<f32>::Coolwarm.sample(0.5), Rgb::new(221, 221, 221));
+    }
+
+    #[test]
+    fn samples_outside_the_unit_range_clamp() {
+        for map in Colormap::<f32>::BUILT_IN {
+            assert_eq!(map.sample(-3.0), map.sample(0.0), "{}", map.name());
+            assert_eq!(map.sample(7.5), map.sample(1.0), "{}", map.name());
+        }
+        let custom = Colormap::Custom(Gradient::new(&[(0.0f32, Rgb::new(10, 20, 30)), (1.0, Rgb::new(40, 50, 60))]));
+        assert_eq!(custom.sample(-1.0), Rgb::new(10, 20, 30));
+        assert_eq!(custom.sample(2.0), Rgb::new(40, 50, 60));
+    }
+}
@@ -0,0 +1,244 @@
+use crate::fluid::Fluid;
+use crate::real::Real;
+
+/// Owns a [`Fluid`] and decides when it advances, so it can be driven by a UI or headless.
+///
+/// Keeps a copy of the initial scene so [`Simulation::reset`] can restore it.
+pub struct Simulation<T: Real = f32> {
+    /// The simulated state; may be edited between steps.
+    pub fluid: Fluid<T>,
+    initial: Fluid<T>,
+
+    /// Whether [`Simulation::update`] advances the fluid.
+    pub playing: bool,
+    /// Multiplier applied to `fluid.time` for every step.
+    pub time_scale: T,
+
+    /// Steps taken since creation or the last reset.
+    pub steps: u64,
+    /// Simulated time since creation or the last reset.
+    pub elapsed: T,
+}
+
+impl<T: Real> Simulation<T> {
+
+    /// Wraps `fluid`, playing, and keeps a copy of it as the initial scene.
+    pub fn new(fluid: Fluid<T>) -> Self {
+        Self {
+            initial: fluid.clone(),
+            fluid,
+            playing: true,
+            time_scale: T::ONE,
+            steps: 0,
+            elapsed: T::ZERO,
+        }
+    }
+
+    /// Starts advancing on [`Simulation::update`].
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    /// Stops advancing on [`Simulation::update`]; stepping by hand still works.
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Switches between playing and paused.
+    pub fn toggle_play(&mut self) {
+        self.playing = !self.playing;
+    }
+
+    /// Call once per frame; only advances while playing.
+    pub fn update(&mut self) {
+        if self.playing {
+            self.step_once();
+        }
+    }
+
+    /// Advances exactly one step, whether playing or paused.
+    pub fn step_once(&mut self) {
+        let dt = self.fluid.time * self.time_scale;
+        self.fluid.step(dt);
+
+        self.steps += 1;
+        self.elapsed += dt;
+    }
+
+    /// Advances exactly `n` steps, whether playing or paused.
+    pub fn step_n(&mut self, n: usize) {
+        for _ in 0..n {
+            self.step_once();
+        }
+    }
+
+    /// Goes back to the scene the simulation was created with, or the last one saved
+    /// with [`Simulation::set_initial_scene`].
+    ///
+    /// Emitter ids handed out since are not reused, so an id held by a frontend never
+    /// ends up naming a different emitter.
+    pub fn reset(&mut self) {
+        let next_stream = self.fluid.next_stream;
+        self.fluid = self.initial.clone();
+        self.fluid.next_stream = self.fluid.next_stream.max(next_stream);
+        self.steps = 0;
+        self.elapsed = T::ZERO;
+    }
+
+    /// Reseeds the initial scene and resets to it, so runs with the same seed match.
+    pub fn reseed(&mut self, seed: u64) {
+        self.initial.reseed(seed);
+        self.reset();
+    }
+
+    /// Makes the current state the one [`Simulation::reset`] returns to.
+    pub fn set_initial_scene(&mut self) {
+        self.initial = self.fluid.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emitter::Emitter;
+
+    fn run(seed: u64, steps: usize) -> Fluid<f32> {
+        let mut sim = Simulation::new(Fluid::with_size(40, 30, 0.5, 0.0, 0.0));
+        sim.reseed(seed);
+        sim.step_n(steps);
+        sim.fluid
+    }
+
+    #[test]
+    fn same_seed_reproduces_every_field() {
+        let (a, b) = (run(3, 20), run(3, 20));
+        assert_eq!(a.px, b.px);
+        assert_eq!(a.py, b.py);
+        assert_eq!(a.pressure, b.pressure);
+        for (x, y) in a.scalars.iter().zip(b.scalars.iter()) {
+            assert_eq!(x.field, y.field);
+        }
+    }
+
+    #[test]
+    fn different_seeds_diverge() {
+        let (a, b) = (run(3, 20), run(4, 20));
+        assert_ne!(a.px, b.px);
+    }
+
+    fn scene() -> Simulation<f32> {
+        let mut fluid = Fluid::with_size(40, 30, 0.5, 0.0, 0.0);
+        fluid.reseed(7);
+        for emitter in &mut fluid.emitters {
+            emitter.particle_rate = 20.0;
+        }
+        Simulation::new(fluid)
+    }
+
+    fn assert_same_state(a: &Fluid<f32>, b: &Fluid<f32>) {
+        assert_eq!(a.px, b.px);
+        assert_eq!(a.py, b.py);
+        assert_eq!(a.pressure, b.pressure);
+        for (x, y) in a.scalars.iter().zip(b.scalars.iter()) {
+            assert_eq!(x.field, y.field);
+        }
+        assert_eq!(a.emitters.len(), b.emitters.len());
+        for (x, y) in a.emitters.iter().zip(&b.emitters) {
+            assert_eq!((x.id(), x.time(), x.x, x.y, x.angle), (y.id(), y.time(), y.x, y.y, y.angle));
+        }
+        let positions = |f: &Fluid<f32>| f.particles.iter().map(|p| (p.x, p.y)).collect::<Vec<_>>();
+        assert_eq!(positions(a), positions(b));
+    }
+
+    #[test]
+    fn update_does_nothing_while_paused() {
+        let mut sim = scene();
+        sim.pause();
+        for _ in 0..5 {
+            sim.update();
+        }
+        assert_eq!((sim.steps, sim.elapsed), (0, 0.0));
+        assert_same_state(&sim.fluid, &scene().fluid);
+
+        sim.play();
+        sim.update();
+        assert_eq!(sim.steps, 1);
+    }
+
+    #[test]
+    fn step_once_and_step_n_count_exactly() {
+        let mut sim = scene();
+        sim.pause();
+        sim.step_once();
+        assert_eq!(sim.steps, 1);
+        sim.step_n(7);
+        assert_eq!(sim.steps, 8);
+        sim.step_n(0);
+        assert_eq!(sim.steps, 8);
+    }
+
+    #[test]
+    fn time_scale_scales_elapsed_and_solver_dt() {
+        let mut sim = scene();
+        sim.time_scale = 0.25;
+        sim.step_n(4);
+
+        let dt = sim.fluid.time * 0.25;
+        assert!((sim.elapsed - 4.0 * dt).abs() < 1e-6);
+
+        // the emitters' clocks only move by the dt the solver was given
+        for emitter in &sim.fluid.emitters {
+            assert!((emitter.time() - 4.0 * dt).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn reset_restores_the_initial_scene_exactly() {
+        let mut sim = scene();
+        sim.step_n(15);
+        sim.fluid.add_scalar("extra");
+        sim.fluid.add_emitter(Emitter::new(5.0, 5.0));
+        sim.reset();
+
+        let mut fresh = scene();
+        assert_eq!((sim.steps, sim.elapsed), (0, 0.0));
+        assert_eq!(sim.fluid.scalars.len(), fresh.fluid.scalars.len());
+        assert_same_state(&sim.fluid, &fresh.fluid);
+
+        // the random streams start over too, so the runs stay in lockstep
+        sim.step_n(15);
+        fresh.step_n(15);
+        assert!(!sim.fluid.particles.is_empty());
+        assert_same_state(&sim.fluid, &fresh.fluid);
+    }
+
+    #[test]
+    fn reset_does_not_reuse_emitter_ids() {
+        let mut sim = Simulation::new(Fluid::<f32>::with_size(20, 20, 0.5, 0.0, 0.0));
+        let i = sim.fluid.add_emitter(Emitter::new(5.0, 5.0));
+        let added = sim.fluid.emitters[i].id();
+
+        sim.reset();
+        assert_eq!(sim.fluid.emitter_index(added), None);
+        let i = sim.fluid.add_emitter(Emitter::new(8.0, 8.0));
+        assert_ne!(sim.fluid.emitters[i].id(), added);
+
+        // the emitters of the initial scene keep theirs
+        let ids: Vec<u64> = sim.fluid.emitters.iter().map(|e| e.id()).collect();
+        assert_eq!(ids, [1, 2, added + 1]);
+    }
+
+    #[test]
+    fn emitters_pushed_directly_get_their_own_stream() {
+        let mut fluid = Fluid::<f32>::with_size(20, 20, 0.5, 0.0, 0.0);
+        fluid.emitters.push(Emitter::new(10.0, 10.0));
+        fluid.emitters.push(Emitter::new(5.0, 5.0));
+        fluid.step(0.5);
+
+        let mut ids: Vec<u64> = fluid.emitters.iter().map(|e| e.id()).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 4);
+        assert!(!ids.contains(&0), "stream 0 belongs to the particles");
+    }
+}
@@ -0,0 +1,41 @@
+use crate::field::Field2D;
+use crate::fluid::Fluid;
+use crate::real::Real;
+
+/// Scalar summaries of a fluid state, used to compare runs and spot instability.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Diagnostics<T: Real = f32> {
+    /// Sum over the grid of every tinted (rendered) scalar field.
+    pub total_dye: T,
+    /// `0.5 * sum(px² + py²)` over the grid.
+    pub kinetic_energy: T,
+    /// Largest velocity magnitude over the grid.
+    pub max_speed: T,
+    /// Largest absolute central-difference divergence over interior cells.
+    pub max_divergence: T,
+}
+
+impl<T: Real> Fluid<T> {
+
+    /// Summarizes the current state.
+    pub fn diagnostics(&self) -> Diagnostics<T> {
+        let mut d = Diagnostics::<T>::default();
+
+        let speeds = self.px.as_slice().iter().zip(self.py.as_slice());
+        for (&u, &v) in speeds {
+            let speed_sq = u * u + v * v;
+            d.kinetic_energy += T::HALF * speed_sq;
+            d.max_speed = d.max_speed.max(speed_sq.sqrt());
+        }
+
+        for dye in self.scalars.iter().filter(|s| s.tint.is_some()) {
+            d.total_dye += dye.field.as_slice().iter().copied().sum();
+        }
+
+        for &div in Field2D::divergence(&self.px, &self.py).as_slice() {
+            d.max_divergence = d.max_divergence.max(div.abs());
+        }
+
+        d
+    }
+}
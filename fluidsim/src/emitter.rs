@@ -0,0 +1,435 @@
+use rand::Rng;
+
+use crate::animation::EmitterAnimation;
+use crate::color::Rgb;
+use crate::motion::Motion;
+use crate::palette::ColorMode;
+use crate::field::Field2D;
+use crate::real::Real;
+use crate::rng::{SimRng, stream_rng};
+use crate::scalar::{DYE_B, DYE_G, DYE_R, ScalarRegistry};
+use crate::shape::{EmitterShape, rotate};
+
+/// Extra scalar an emitter adds besides its dye color, looked up by field name.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScalarSource<T: Real = f32> {
+    /// Scalar field to feed.
+    pub name: String,
+    /// Amount per second at full shape weight, scaled by the emitter strength.
+    pub amount: T,
+}
+
+/// What an emitter does to the fluid under its footprint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum EmitterKind {
+    /// Adds `force` momentum per second along the heading, no dye.
+    Fan,
+    /// Adds dye and scalar sources only.
+    DyeSource,
+    /// Adds dye and pulls the velocity toward `jet_speed` along the heading,
+    /// at `jet_response` per second.
+    #[default]
+    Jet,
+    /// Adds `force` momentum per second tangentially around the center,
+    /// counter-clockwise on screen for positive force.
+    Vortex,
+    /// Removes every scalar at `drain_rate` per second and draws fluid in: the pressure
+    /// projection gives it a divergence of `-force` per second at full shape weight.
+    Sink,
+}
+
+impl EmitterKind {
+
+    /// Every kind, for pickers.
+    pub const ALL: [EmitterKind; 5] = [EmitterKind::Fan, EmitterKind::DyeSource, EmitterKind::Jet, EmitterKind::Vortex, EmitterKind::Sink];
+
+    /// Label shown in pickers.
+    pub fn name(self) -> &'static str {
+        match self {
+            EmitterKind::Fan => "Fan",
+            EmitterKind::DyeSource => "Dye Source",
+            EmitterKind::Jet => "Jet",
+            EmitterKind::Vortex => "Vortex",
+            EmitterKind::Sink => "Sink",
+        }
+    }
+
+    /// Whether `color`, `dye_rate` and `sources` are used.
+    pub fn emits_dye(self) -> bool {
+        matches!(self, EmitterKind::DyeSource | EmitterKind::Jet)
+    }
+
+    /// Whether `angle` is used.
+    pub fn is_directional(self) -> bool {
+        matches!(self, EmitterKind::Fan | EmitterKind::Jet)
+    }
+}
+
+/// A source that injects dye and/or momentum into the fluid at rates per second
+/// of simulated time, so the result does not depend on the timestep.
+#[derive(Clone)]
+pub struct Emitter<T: Real = f32> {
+    /// Label shown in the viewer; need not be unique.
+    pub name: String,
+    /// Center in grid coordinates, where cell `(i, j)` sits at `(i, j)`; need not be on a cell.
+    pub x: T,
+    /// Vertical coordinate of the center, growing downward.
+    pub y: T,
+    /// Multiplier on every rate below.
+    pub strength: T,
+    /// What the emitter does to the fluid.
+    pub kind: EmitterKind,
+    /// Dye per second at full shape weight, split into channels by `color`.
+    pub dye_rate: T,
+    /// Momentum per second for fans and vortices; for sinks, the rate fluid is drawn
+    /// in at full shape weight.
+    pub force: T,
+    /// Target speed of a jet, in cells per unit time.
+    pub jet_speed: T,
+    /// How quickly a jet reaches its target speed, per second.
+    pub jet_response: T,
+    /// Fraction of each scalar a sink removes per second at full shape weight.
+    pub drain_rate: T,
+    /// Footprint the dye and momentum are spread over.
+    pub shape: EmitterShape<T>,
+    /// Heading of fans and jets, and orientation of the shape, in radians.
+    pub angle: T,
+    /// Radians per unit of simulated time.
+    pub rotation_speed: T,
+    /// Injected into the dye channels; overwritten every update unless `color_mode` is solid.
+    pub color: Rgb,
+    /// Where `color` comes from over time.
+    pub color_mode: ColorMode<T>,
+    /// Other scalar fields to feed, e.g. temperature; names missing from the fluid are ignored.
+    pub sources: Vec<ScalarSource<T>>,
+
+    /// Keyframes, schedule and pulsing, played on the emitter's own clock.
+    pub animation: EmitterAnimation<T>,
+    /// Path, orbit or wander; takes precedence over a keyframed position.
+    pub motion: Motion<T>,
+    /// Particles released per second at full strength, see [`crate::ParticleSystem`].
+    pub particle_rate: T,
+
+    time: T,
+    activity: T,
+    color_cycle: Option<u64>,
+    pub(crate) particle_carry: T,
+
+    pub(crate) stream: u64,
+    pub(crate) rng: SimRng,
+}
+
+impl<T: Real> Emitter<T> {
+
+    /// Jet at `(x, y)` with default settings; [`crate::Fluid::add_emitter`] gives it its own random stream.
+    pub fn new(x: T, y: T) -> Self {
+        Self {
+            name: String::from("Emitter"),
+            x,
+            y,
+            strength: T::ONE,
+            dye_rate: T::from_f32(2.0),
+            kind: EmitterKind::default(),
+            force: T::from_f32(2.0),
+            jet_speed: T::from_f32(4.0),
+            jet_response: T::from_f32(5.0),
+            drain_rate: T::from_f32(2.0),
+            shape: EmitterShape::default(),
+            angle: T::ZERO,
+            rotation_speed: T::ZERO,
+            color: Rgb::WHITE,
+            color_mode: ColorMode::Solid,
+            sources: Vec::new(),
+            animation: EmitterAnimation::default(),
+            motion: Motion::Static,
+            particle_rate: T::ZERO,
+            time: T::ZERO,
+            activity: T::ONE,
+            color_cycle: None,
+            particle_carry: T::ZERO,
+            stream: 0,
+            rng: stream_rng(0, 0),
+        }
+    }
+
+    /// Stable identifier, unique among the emitters of one [`crate::Fluid`] and kept
+    /// when others are removed; 0 until the emitter is added.
+    pub fn id(&self) -> u64 {
+        self.stream
+    }
+
+    /// Simulated time since the emitter was created; the clock its animation runs on.
+    pub fn time(&self) -> T {
+        self.time
+    }
+
+    /// Current output factor in `0..=1` from the animation schedule and pulse.
+    pub fn activity(&self) -> T {
+        self.activity
+    }
+
+    /// Advances the emitter's clock by `dt` and applies its animation and motion, falling
+    /// back to `rotation_speed` when the angle is not keyframed.
+    pub fn update(&mut self, dt: T) {
+        self.time += dt;
+
+        let anim = &self.animation;
+        let t = anim.track_time(self.time);
+
+        if let Some((x, y)) = anim.position.sample(t) {
+            self.x = x;
+            self.y = y;
+        }
+        if let Some(strength) = anim.strength.sample(t) {
+            self.strength = strength;
+        }
+        if let Some(color) = anim.color.sample(t) {
+            self.color = color;
+        }
+        if let Some(radius) = anim.radius.sample(t) {
+            self.shape.set_radius(radius);
+        }
+        self.activity = anim.activity(self.time);
+
+        if let Some((x, y)) = self.motion.advance(self.time, dt) {
+            self.x = x;
+            self.y = y;
+        }
+
+        self.update_color();
+
+        if let Some(angle) = self.animation.angle.sample_angle(t) {
+            self.angle = angle;
+            return;
+        }
+
+        self.angle += self.rotation_speed * dt;
+
+        if self.angle > T::TAU {
+            self.angle -= T::TAU;
+        } else if self.angle < T::ZERO {
+            self.angle += T::TAU;
+        }
+    }
+
+    /// Applies the emitter's [`EmitterKind`] to the cells it covers for a step of length `dt`.
+    pub fn inject(&mut self, scalars: &mut ScalarRegistry<T>, px: &mut Field2D<T>, py: &mut Field2D<T>, dt: T) {
+
+        let level = self.strength * self.activity;
+        if level <= T::ZERO {
+            return;
+        }
+        let scale = level * dt;
+
+        // resolve field names once rather than per cell
+        let [r, g, b] = self.color.to_f32();
+        let dye = [(DYE_R, r), (DYE_G, g), (DYE_B, b)].map(|(name, c)| (name, T::from_f32(c) * self.dye_rate));
+        let extra = self.sources.iter().map(|s| (s.name.as_str(), s.amount));
+        let targets: Vec<(usize, T)> = if self.kind.emits_dye() {
+            dye.into_iter().chain(extra)
+                .filter_map(|(name, amount)| scalars.index_of(name).map(|idx| (idx, amount * scale)))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        // exact fraction of the gap a first order relaxation closes in dt
+        let blend = T::ONE - (-self.jet_response * dt).exp();
+        let drain = self.drain_rate * dt;
+
+        // splat over every cell the shape can reach, weighting by the offset of the
+        // cell center from the (sub-cell) emitter position
+        let extent = self.shape.extent();
+        let (x_min, x_max) = ((self.x - extent).floor().to_isize(), (self.x + extent).floor().to_isize() + 1);
+        let (y_min, y_max) = ((self.y - extent).floor().to_isize(), (self.y + extent).floor().to_isize() + 1);
+
+        for y in y_min..=y_max {
+            for x in x_min..=x_max {
+                let dx = T::from_f64(x as f64) - self.x;
+                let dy = T::from_f64(y as f64) - self.y;
+
+                let (u, v) = rotate(dx, dy, self.angle);
+                let weight = self.shape.weight(u, v);
+
+                if px.in_bounds(x, y) && weight > T::ZERO {
+                    let (x, y) = (x as usize, y as usize);
+
+                    // inject colored smoke and other scalars
+                    for &(idx, amount) in &targets {
+                        scalars.fields[idx].field[(x, y)] += amount * weight;
+                    }
+
+                    let push = self.force * scale * weight;
+                    let r = (dx * dx + dy * dy).sqrt();
+
+                    match self.kind {
+                        EmitterKind::Fan => {
+                            let angle = self.jittered_angle();
+                            px[(x, y)] += angle.cos() * push;
+                            py[(x, y)] += angle.sin() * push;
+                        }
+                        EmitterKind::Jet => {
+                            let angle = self.jittered_angle();
+                            let speed = self.jet_speed * self.strength;
+                            let w = blend * weight.min(T::ONE) * self.activity;
+                            let (u0, v0) = (px[(x, y)], py[(x, y)]);
+                            px[(x, y)] = u0 + (angle.cos() * speed - u0) * w;
+                            py[(x, y)] = v0 + (angle.sin() * speed - v0) * w;
+                        }
+                        EmitterKind::Vortex if r > T::ZERO => {
+                            // y points down on screen, so (dy, -dx) turns counter-clockwise there
+                            px[(x, y)] += dy / r * push;
+                            py[(x, y)] -= dx / r * push;
+                        }
+                        EmitterKind::Sink => {
+                            let keep = (-drain * level * weight).exp();
+                            for scalar in scalars.iter_mut() {
+                                scalar.field[(x, y)] *= keep;
+                            }
+                        }
+                        EmitterKind::Vortex | EmitterKind::DyeSource => {}
+                    }
+                }
+            }
+        }
+    }
+
+    /// Adds what a sink draws in per second to each cell of `inflow`, which the pressure
+    /// projection turns into converging flow. An inward push on the velocity would have
+    /// no curl, so the projection would remove it again. Does nothing for other kinds.
+    pub(crate) fn add_inflow(&self, inflow: &mut Field2D<T>) {
+        let level = self.strength * self.activity;
+        if self.kind != EmitterKind::Sink || level <= T::ZERO {
+            return;
+        }
+
+        let extent = self.shape.extent();
+        let (x_min, x_max) = ((self.x - extent).floor().to_isize(), (self.x + extent).floor().to_isize() + 1);
+        let (y_min, y_max) = ((self.y - extent).floor().to_isize(), (self.y + extent).floor().to_isize() + 1);
+
+        for y in y_min..=y_max {
+            for x in x_min..=x_max {
+                let dx = T::from_f64(x as f64) - self.x;
+                let dy = T::from_f64(y as f64) - self.y;
+                let (u, v) = rotate(dx, dy, self.angle);
+                let weight = self.shape.weight(u, v);
+                if inflow.in_bounds(x, y) && weight > T::ZERO {
+                    inflow[(x as usize, y as usize)] += self.force * level * weight;
+                }
+            }
+        }
+    }
+
+    fn update_color(&mut self) {
+        let time = self.time;
+        match &self.color_mode {
+            ColorMode::Solid => {}
+            ColorMode::HueCycle { speed, saturation, value } => {
+                self.color = Rgb::from_hsv((*speed * time).to_f32(), saturation.to_f32(), value.to_f32());
+            }
+            ColorMode::Gradient { gradient, period } => {
+                let t = if *period > T::ZERO {
+                    let cycles = time / *period;
+                    cycles - cycles.floor()
+                } else {
+                    T::ZERO
+                };
+                self.color = gradient.sample(t);
+            }
+            ColorMode::RandomPerPulse { saturation, value } => {
+                let cycle = self.animation.pulse.cycle(time);
+                if self.color_cycle != Some(cycle) {
+                    let (s, v) = (saturation.to_f32(), value.to_f32());
+                    self.color = Rgb::from_hsv(self.rng.r#gen::<f32>(), s, v);
+                    self.color_cycle = Some(cycle);
+                }
+            }
+        }
+    }
+
+    //heading plus a small random spread so jets don't look perfectly laminar
+
+    fn jittered_angle(&mut self) -> T {
+        let spread = (T::from_f64(self.rng.r#gen::<f64>()) - T::HALF) * T::HALF;
+        self.angle + spread
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animation::Pulse;
+    use crate::fluid::Fluid;
+
+    #[test]
+    fn sink_draws_fluid_in() {
+        let mut fluid = Fluid::<f32>::with_size(40, 40, 0.5, 0.0, 0.0);
+        fluid.emitters.clear();
+        let mut sink = Emitter::new(20.0, 20.0);
+        sink.kind = EmitterKind::Sink;
+        fluid.add_emitter(sink);
+        fluid.step(0.5);
+
+        assert!(fluid.px[(16, 20)] > 0.0 && fluid.px[(24, 20)] < 0.0);
+        assert!(fluid.py[(20, 16)] > 0.0 && fluid.py[(20, 24)] < 0.0);
+        assert!(fluid.diagnostics().max_divergence > 0.1);
+    }
+
+    #[test]
+    fn sink_drains_scalars() {
+        let mut fluid = Fluid::<f32>::with_size(20, 20, 0.5, 0.0, 0.0);
+        fluid.emitters.clear();
+        for scalar in fluid.scalars.iter_mut() {
+            scalar.dissipation = 1.0;
+            scalar.field.fill(1.0);
+        }
+        let mut sink = Emitter::new(10.0, 10.0);
+        sink.kind = EmitterKind::Sink;
+        fluid.add_emitter(sink);
+        fluid.step(0.5);
+
+        let dye = &fluid.scalars.get(DYE_R).unwrap().field;
+        assert!(dye[(10, 10)] < 0.5);
+        assert!(dye[(2, 2)] > 0.99);
+    }
+
+    #[test]
+    fn hue_cycle_turns_with_time() {
+        let mut e = Emitter::<f32>::new(0.0, 0.0);
+        e.color_mode = ColorMode::HueCycle { speed: 0.5, saturation: 1.0, value: 1.0 };
+        e.update(0.5);
+        assert_eq!(e.color, Rgb::from_hsv(0.25, 1.0, 1.0));
+        e.update(0.5);
+        assert_eq!(e.color, Rgb::from_hsv(0.5, 1.0, 1.0));
+
+        // a full turn after two seconds
+        e.update(1.0);
+        assert_eq!(e.color, Rgb::new(255, 0, 0));
+    }
+
+    fn random_per_pulse(seed: u64) -> Vec<Rgb> {
+        let mut e = Emitter::<f32>::new(0.0, 0.0);
+        e.rng = stream_rng(seed, 1);
+        e.animation.pulse = Pulse::Square { period: 1.0, duty: 0.5 };
+        e.color_mode = ColorMode::RandomPerPulse { saturation: 1.0, value: 1.0 };
+        (0..12).map(|_| {
+            e.update(0.25);
+            e.color
+        }).collect()
+    }
+
+    #[test]
+    fn random_per_pulse_changes_only_on_a_new_pulse() {
+        let colors = random_per_pulse(5);
+        // four updates per pulse, the first crossing into the next one
+        for (i, pair) in colors.windows(2).enumerate() {
+            if (i + 2) % 4 == 0 {
+                assert_ne!(pair[0], pair[1], "update {}", i + 1);
+            } else {
+                assert_eq!(pair[0], pair[1], "update {}", i + 1);
+            }
+        }
+        assert_eq!(colors, random_per_pulse(5));
+        assert_ne!(colors, random_per_pulse(6));
+    }
+}
@@ -0,0 +1,404 @@
+use std::ops::{Index, IndexMut};
+use std::slice::{ChunksExact, ChunksExactMut};
+
+use crate::real::Real;
+
+/// Row-major 2D grid of values with unit cell spacing.
+///
+/// Differential operators use central differences on interior cells and leave the
+/// one-cell border at zero, matching how the solver treats the domain walls.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field2D<T: Real = f32> {
+    width: usize,
+    height: usize,
+    data: Vec<T>,
+}
+
+impl<T: Real> Field2D<T> {
+
+    /// Field of zeros.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self::filled(width, height, T::ZERO)
+    }
+
+    /// Field with every cell set to `value`.
+    pub fn filled(width: usize, height: usize, value: T) -> Self {
+        Self { width, height, data: vec![value; width * height] }
+    }
+
+    /// Wraps existing row-major data.
+    ///
+    /// # Panics
+    /// If `data.len() != width * height`.
+    pub fn from_vec(width: usize, height: usize, data: Vec<T>) -> Self {
+        assert_eq!(data.len(), width * height, "field data does not match {width}x{height}");
+        Self { width, height, data }
+    }
+
+    /// Cells per row.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of rows.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Row-major index of cell `(x, y)`; not bounds checked against the width.
+    pub fn index(&self, x: usize, y: usize) -> usize {
+        x + y * self.width
+    }
+
+    /// Whether `(x, y)` is a cell of the grid.
+    pub fn in_bounds(&self, x: isize, y: isize) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height
+    }
+
+    /// Value at `(x, y)`, or `None` outside the grid.
+    pub fn get(&self, x: usize, y: usize) -> Option<T> {
+        if x < self.width && y < self.height {
+            Some(self.data[self.index(x, y)])
+        } else {
+            None
+        }
+    }
+
+    /// Mutable value at `(x, y)`, or `None` outside the grid.
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        if x < self.width && y < self.height {
+            let idx = self.index(x, y);
+            Some(&mut self.data[idx])
+        } else {
+            None
+        }
+    }
+
+    /// # Safety
+    /// `x < width` and `y < height` must hold.
+    pub unsafe fn get_unchecked(&self, x: usize, y: usize) -> T {
+        unsafe { *self.data.get_unchecked(x + y * self.width) }
+    }
+
+    /// # Safety
+    /// `x < width` and `y < height` must hold.
+    pub unsafe fn get_unchecked_mut(&mut self, x: usize, y: usize) -> &mut T {
+        let idx = x + y * self.width;
+        unsafe { self.data.get_unchecked_mut(idx) }
+    }
+
+    /// Value at `(x, y)` with the coordinates clamped into the grid; 0 for an empty field.
+    pub fn get_clamped(&self, x: isize, y: isize) -> T {
+        if self.data.is_empty() {
+            return T::ZERO;
+        }
+        let x = x.clamp(0, self.width as isize - 1) as usize;
+        let y = y.clamp(0, self.height as isize - 1) as usize;
+        self.data[self.index(x, y)]
+    }
+
+    /// Cells in row-major order.
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Cells in row-major order, mutably.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    /// Sets every cell to `value`.
+    pub fn fill(&mut self, value: T) {
+        self.data.fill(value);
+    }
+
+    /// Rows from `y = 0` down.
+    pub fn rows(&self) -> ChunksExact<'_, T> {
+        self.data.chunks_exact(self.width.max(1))
+    }
+
+    /// Rows from `y = 0` down, mutably.
+    pub fn rows_mut(&mut self) -> ChunksExactMut<'_, T> {
+        self.data.chunks_exact_mut(self.width.max(1))
+    }
+
+    /// Bilinear interpolation at a continuous position where cell `(i, j)` sits at `(i, j)`.
+    /// Positions outside the grid are clamped to the border; an empty field samples as 0.
+    pub fn sample_bilinear(&self, x: T, y: T) -> T {
+        if self.data.is_empty() {
+            return T::ZERO;
+        }
+        let x = x.clamp(T::ZERO, T::from_usize(self.width - 1));
+        let y = y.clamp(T::ZERO, T::from_usize(self.height - 1));
+
+        let i0 = x.floor().to_usize();
+        let j0 = y.floor().to_usize();
+        let i1 = (i0 + 1).min(self.width - 1);
+        let j1 = (j0 + 1).min(self.height - 1);
+
+        let s1 = x - T::from_usize(i0);
+        let s0 = T::ONE - s1;
+        let t1 = y - T::from_usize(j0);
+        let t0 = T::ONE - t1;
+
+        s0 * (t0 * self[(i0, j0)] + t1 * self[(i0, j1)]) + s1 * (t0 * self[(i1, j0)] + t1 * self[(i1, j1)])
+    }
+
+    /// Adds `amount` at a continuous position, spread over the four surrounding cells
+    /// with bilinear weights (the adjoint of [`Field2D::sample_bilinear`]).
+    pub fn splat_bilinear(&mut self, x: T, y: T, amount: T) {
+        let xf = x.floor();
+        let yf = y.floor();
+        let s1 = x - xf;
+        let t1 = y - yf;
+        let (i, j) = (xf.to_isize(), yf.to_isize());
+
+        for (di, dj, w) in [(0, 0, (T::ONE - s1) * (T::ONE - t1)), (1, 0, s1 * (T::ONE - t1)), (0, 1, (T::ONE - s1) * t1), (1, 1, s1 * t1)] {
+            if self.in_bounds(i + di, j + dj) {
+                let idx = self.index((i + di) as usize, (j + dj) as usize);
+                self.data[idx] += amount * w;
+            }
+        }
+    }
+
+    /// Adds `amount` around a continuous position with Gaussian weights `exp(-(r / radius)²)`,
+    /// so the sum deposited is about `amount * π * radius²`.
+    pub fn splat_gaussian(&mut self, x: T, y: T, radius: T, amount: T) {
+        let radius = radius.max(T::from_f32(0.1));
+        let reach = (radius * T::from_f32(2.0)).to_isize() + 1;
+        let (cx, cy) = (x.floor().to_isize(), y.floor().to_isize());
+
+        for j in cy - reach..=cy + reach + 1 {
+            for i in cx - reach..=cx + reach + 1 {
+                if self.in_bounds(i, j) {
+                    let dx = T::from_f64(i as f64) - x;
+                    let dy = T::from_f64(j as f64) - y;
+                    let w = (-(dx * dx + dy * dy) / (radius * radius)).exp();
+                    let idx = self.index(i as usize, j as usize);
+                    self.data[idx] += amount * w;
+                }
+            }
+        }
+    }
+
+    /// Catmull-Rom bicubic interpolation; sharper than bilinear but may overshoot.
+    /// An empty field samples as 0.
+    pub fn sample_bicubic(&self, x: T, y: T) -> T {
+        if self.data.is_empty() {
+            return T::ZERO;
+        }
+        let x = x.clamp(T::ZERO, T::from_usize(self.width - 1));
+        let y = y.clamp(T::ZERO, T::from_usize(self.height - 1));
+
+        let xf = x.floor();
+        let yf = y.floor();
+        let tx = x - xf;
+        let ty = y - yf;
+        let i = xf.to_usize() as isize;
+        let j = yf.to_usize() as isize;
+
+        let mut col = [T::ZERO; 4];
+        for (n, c) in col.iter_mut().enumerate() {
+            let row = j - 1 + n as isize;
+            *c = catmull_rom(
+                self.get_clamped(i - 1, row),
+                self.get_clamped(i, row),
+                self.get_clamped(i + 1, row),
+                self.get_clamped(i + 2, row),
+                tx,
+            );
+        }
+
+        catmull_rom(col[0], col[1], col[2], col[3], ty)
+    }
+
+    /// Central-difference gradient at a cell, one-sided at the border.
+    pub fn gradient(&self, x: usize, y: usize) -> (T, T) {
+        let (xi, yi) = (x as isize, y as isize);
+        let dx = if x == 0 || x + 1 == self.width { T::ONE } else { T::HALF };
+        let dy = if y == 0 || y + 1 == self.height { T::ONE } else { T::HALF };
+
+        (
+            (self.get_clamped(xi + 1, yi) - self.get_clamped(xi - 1, yi)) * dx,
+            (self.get_clamped(xi, yi + 1) - self.get_clamped(xi, yi - 1)) * dy,
+        )
+    }
+
+    /// Five-point Laplacian.
+    pub fn laplacian(&self) -> Self {
+        let four = T::from_f32(4.0);
+        self.interior_map(|f, i, j| {
+            f[(i + 1, j)] + f[(i - 1, j)] + f[(i, j + 1)] + f[(i, j - 1)] - four * f[(i, j)]
+        })
+    }
+
+    /// Divergence `∂u/∂x + ∂v/∂y` of the vector field `(u, v)`.
+    pub fn divergence(u: &Self, v: &Self) -> Self {
+        u.interior_map(|u, i, j| {
+            T::HALF * (u[(i + 1, j)] - u[(i - 1, j)] + v[(i, j + 1)] - v[(i, j - 1)])
+        })
+    }
+
+    /// Scalar curl (vorticity) `∂v/∂x - ∂u/∂y` of the vector field `(u, v)`.
+    pub fn curl(u: &Self, v: &Self) -> Self {
+        u.interior_map(|u, i, j| {
+            T::HALF * (v[(i + 1, j)] - v[(i - 1, j)] - u[(i, j + 1)] + u[(i, j - 1)])
+        })
+    }
+
+    fn interior_map(&self, f: impl Fn(&Self, usize, usize) -> T) -> Self {
+        let mut out = Self::new(self.width, self.height);
+        for j in 1..self.height.saturating_sub(1) {
+            for i in 1..self.width.saturating_sub(1) {
+                let idx = out.index(i, j);
+                out.data[idx] = f(self, i, j);
+            }
+        }
+        out
+    }
+}
+
+pub(crate) fn catmull_rom<T: Real>(p0: T, p1: T, p2: T, p3: T, t: T) -> T {
+    let two = T::from_f32(2.0);
+    let three = T::from_f32(3.0);
+    let four = T::from_f32(4.0);
+    let five = T::from_f32(5.0);
+
+    let a = two * p1;
+    let b = p2 - p0;
+    let c = two * p0 - five * p1 + four * p2 - p3;
+    let d = three * (p1 - p2) + p3 - p0;
+
+    T::HALF * (a + t * (b + t * (c + t * d)))
+}
+
+impl<T: Real> Index<(usize, usize)> for Field2D<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        assert!(x < self.width && y < self.height, "cell ({x}, {y}) outside {}x{} field", self.width, self.height);
+        &self.data[x + y * self.width]
+    }
+}
+
+impl<T: Real> IndexMut<(usize, usize)> for Field2D<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        assert!(x < self.width && y < self.height, "cell ({x}, {y}) outside {}x{} field", self.width, self.height);
+        &mut self.data[x + y * self.width]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //f(x, y) = 2x + 3y sampled on a grid
+    fn ramp(width: usize, height: usize) -> Field2D<f64> {
+        let data = (0..height).flat_map(|y| (0..width).map(move |x| 2.0 * x as f64 + 3.0 * y as f64)).collect();
+        Field2D::from_vec(width, height, data)
+    }
+
+    #[test]
+    fn indexing_is_row_major() {
+        let mut f = Field2D::<f32>::new(4, 3);
+        f[(1, 2)] = 5.0;
+        assert_eq!(f.as_slice()[f.index(1, 2)], 5.0);
+        assert_eq!(f.index(1, 2), 9);
+        assert_eq!(f.get(1, 2), Some(5.0));
+        assert_eq!(f.get(4, 0), None);
+        assert_eq!(f.rows().nth(2).unwrap(), &[0.0, 5.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_vec_checks_length() {
+        Field2D::<f32>::from_vec(3, 3, vec![0.0; 8]);
+    }
+
+    #[test]
+    fn get_clamped_holds_the_border() {
+        let f = ramp(4, 3);
+        assert_eq!(f.get_clamped(-5, -1), f[(0, 0)]);
+        assert_eq!(f.get_clamped(10, 10), f[(3, 2)]);
+    }
+
+    #[test]
+    fn empty_fields_sample_as_zero() {
+        for (w, h) in [(0, 0), (0, 4), (4, 0)] {
+            let f = Field2D::<f32>::new(w, h);
+            assert_eq!(f.sample_bilinear(1.5, 2.0), 0.0);
+            assert_eq!(f.sample_bicubic(-1.0, 3.0), 0.0);
+            assert_eq!(f.get_clamped(2, 2), 0.0);
+            assert_eq!(f.gradient(0, 0), (0.0, 0.0));
+            assert_eq!(f.rows().count(), 0);
+        }
+    }
+
+    #[test]
+    fn bilinear_is_exact_on_linear_fields() {
+        let f = ramp(5, 4);
+        assert_eq!(f.sample_bilinear(2.0, 1.0), f[(2, 1)]);
+        assert!((f.sample_bilinear(1.25, 2.5) - (2.0 * 1.25 + 3.0 * 2.5)).abs() < 1e-12);
+        // clamped to the grid outside it
+        assert_eq!(f.sample_bilinear(-3.0, 9.0), f[(0, 3)]);
+    }
+
+    #[test]
+    fn bicubic_matches_on_cells_and_linear_fields() {
+        let f = ramp(6, 6);
+        assert!((f.sample_bicubic(3.0, 2.0) - f[(3, 2)]).abs() < 1e-12);
+        assert!((f.sample_bicubic(2.5, 2.75) - (2.0 * 2.5 + 3.0 * 2.75)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn splat_bilinear_is_adjoint_of_sampling() {
+        let mut f = Field2D::<f64>::new(4, 4);
+        f.splat_bilinear(1.25, 2.5, 2.0);
+        let total: f64 = f.as_slice().iter().sum();
+        assert!((total - 2.0).abs() < 1e-12);
+        assert!((f[(1, 2)] - 2.0 * 0.75 * 0.5).abs() < 1e-12);
+        assert!((f[(2, 3)] - 2.0 * 0.25 * 0.5).abs() < 1e-12);
+
+        // weight that falls off the grid is dropped
+        let mut edge = Field2D::<f64>::new(4, 4);
+        edge.splat_bilinear(3.5, 0.0, 1.0);
+        assert!((edge.as_slice().iter().sum::<f64>() - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn splat_gaussian_deposits_about_pi_r_squared() {
+        let mut f = Field2D::<f64>::new(40, 40);
+        f.splat_gaussian(20.3, 19.6, 3.0, 1.0);
+        let total: f64 = f.as_slice().iter().sum();
+        let expected = std::f64::consts::PI * 9.0;
+        assert!((total - expected).abs() / expected < 0.02, "{total}");
+    }
+
+    #[test]
+    fn operators_on_a_linear_field() {
+        let f = ramp(5, 5);
+        assert_eq!(f.gradient(2, 2), (2.0, 3.0));
+        // one-sided at the border
+        assert_eq!(f.gradient(0, 4), (2.0, 3.0));
+
+        let lap = f.laplacian();
+        assert!(lap.as_slice().iter().all(|&v| v == 0.0));
+    }
+
+    #[test]
+    fn divergence_and_curl() {
+        // u = x, v = y spreads out; u = -y, v = x turns counter-clockwise
+        let (w, h) = (5, 5);
+        let x = Field2D::from_vec(w, h, (0..w * h).map(|i| (i % w) as f64).collect());
+        let y = Field2D::from_vec(w, h, (0..w * h).map(|i| (i / w) as f64).collect());
+        let neg_y = Field2D::from_vec(w, h, y.as_slice().iter().map(|v| -v).collect());
+
+        let div = Field2D::divergence(&x, &y);
+        assert_eq!(div[(2, 2)], 2.0);
+        assert_eq!(div[(0, 2)], 0.0, "border is left at zero");
+        assert_eq!(Field2D::curl(&x, &y)[(2, 2)], 0.0);
+
+        assert_eq!(Field2D::divergence(&neg_y, &x)[(2, 2)], 0.0);
+        assert_eq!(Field2D::curl(&neg_y, &x)[(2, 2)], 2.0);
+    }
+}
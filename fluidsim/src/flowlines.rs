@@ -0,0 +1,246 @@
+use std::collections::VecDeque;
+
+use crate::fluid::Fluid;
+use crate::real::Real;
+
+impl<T: Real> Fluid<T> {
+
+    /// Bilinearly interpolated velocity at a continuous grid position.
+    pub fn velocity_at(&self, x: T, y: T) -> (T, T) {
+        (self.px.sample_bilinear(x, y), self.py.sample_bilinear(x, y))
+    }
+
+    /// Whether `(x, y)` lies on the grid and outside every obstacle.
+    pub fn is_open(&self, x: T, y: T) -> bool {
+        let max_x = T::from_usize(self.width - 1);
+        let max_y = T::from_usize(self.height - 1);
+        if !(x >= T::ZERO && x <= max_x && y >= T::ZERO && y <= max_y) {
+            return false;
+        }
+        let (i, j) = ((x + T::HALF).to_usize(), (y + T::HALF).to_usize());
+        self.obstacles[(i.min(self.width - 1), j.min(self.height - 1))] <= T::HALF
+    }
+
+    /// Moves a massless point through the current velocity for `dt` with classic RK4.
+    pub fn advect_point(&self, (x, y): (T, T), dt: T) -> (T, T) {
+        rk4((x, y), dt, |x, y| self.velocity_at(x, y))
+    }
+
+    /// Streamline of the current (frozen) velocity from `seed`, in steps of `step` cells
+    /// along the flow direction; a negative `step` traces upstream. Stops after `max_steps`,
+    /// on leaving the grid or entering an obstacle, or where the flow is still.
+    pub fn streamline(&self, seed: (T, T), step: T, max_steps: usize) -> Vec<(T, T)> {
+        let still = T::from_f32(1e-4);
+
+        //unit direction field, so points are evenly spaced whatever the speed
+
+        let direction = |x: T, y: T| {
+            let (u, v) = self.velocity_at(x, y);
+            let speed = (u * u + v * v).sqrt();
+            if speed < still { (T::ZERO, T::ZERO) } else { (u / speed, v / speed) }
+        };
+
+        let mut line = vec![seed];
+        let mut p = seed;
+        for _ in 0..max_steps {
+            let (u, v) = direction(p.0, p.1);
+            if u == T::ZERO && v == T::ZERO {
+                break;
+            }
+            p = rk4(p, step, direction);
+            if !self.is_open(p.0, p.1) {
+                break;
+            }
+            line.push(p);
+        }
+        line
+    }
+}
+
+pub(crate) fn rk4<T: Real>((x, y): (T, T), h: T, f: impl Fn(T, T) -> (T, T)) -> (T, T) {
+    let two = T::from_f32(2.0);
+    let (k1x, k1y) = f(x, y);
+    let (k2x, k2y) = f(x + T::HALF * h * k1x, y + T::HALF * h * k1y);
+    let (k3x, k3y) = f(x + T::HALF * h * k2x, y + T::HALF * h * k2y);
+    let (k4x, k4y) = f(x + h * k3x, y + h * k3y);
+    let sixth = h / T::from_f32(6.0);
+    (x + sixth * (k1x + two * k2x + two * k3x + k4x), y + sixth * (k1y + two * k2y + two * k3y + k4y))
+}
+
+/// Time-dependent traces from fixed seed points, advanced alongside the fluid.
+///
+/// A pathline is the trajectory of one particle released from a seed; a streakline
+/// joins every particle released from a seed so far, like dye from a nozzle. Both
+/// keep only their newest `max_points` points. In steady flow they match the streamline.
+#[derive(Clone, Debug, Default)]
+pub struct FlowTraces<T: Real = f32> {
+    /// Points kept per trace; at least 2 are kept.
+    pub max_points: usize,
+    seeds: Vec<(T, T)>,
+    //oldest point first
+    pathlines: Vec<VecDeque<(T, T)>>,
+    //newest particle first, so the line runs away from the seed
+    streaklines: Vec<VecDeque<(T, T)>>,
+}
+
+impl<T: Real> FlowTraces<T> {
+
+    /// Traces keeping `max_points` points each, with no seeds yet.
+    pub fn new(max_points: usize) -> Self {
+        Self { max_points, ..Self::default() }
+    }
+
+    /// Seed points, in grid coordinates.
+    pub fn seeds(&self) -> &[(T, T)] {
+        &self.seeds
+    }
+
+    /// Adds a seed and starts its traces there.
+    pub fn add_seed(&mut self, seed: (T, T)) {
+        self.seeds.push(seed);
+        self.pathlines.push(VecDeque::from([seed]));
+        self.streaklines.push(VecDeque::new());
+    }
+
+    /// Replaces the seeds, discarding all traces.
+    pub fn set_seeds(&mut self, seeds: Vec<(T, T)>) {
+        self.seeds.clear();
+        self.pathlines.clear();
+        self.streaklines.clear();
+        for seed in seeds {
+            self.add_seed(seed);
+        }
+    }
+
+    /// Seeds on a regular grid `spacing` cells apart, centered on the fluid.
+    pub fn seed_grid(&mut self, width: usize, height: usize, spacing: usize) {
+        let spacing = spacing.max(1);
+        let seeds = (spacing / 2..height).step_by(spacing)
+            .flat_map(|j| (spacing / 2..width).step_by(spacing).map(move |i| (T::from_usize(i), T::from_usize(j))))
+            .collect();
+        self.set_seeds(seeds);
+    }
+
+    /// Restarts every trace from its seed.
+    pub fn restart(&mut self) {
+        let seeds = std::mem::take(&mut self.seeds);
+        self.set_seeds(seeds);
+    }
+
+    /// Moves every traced particle through `fluid` for `dt`, then releases a new streak
+    /// particle at each seed. Particles that leave the grid or hit an obstacle stop.
+    pub fn advance(&mut self, fluid: &Fluid<T>, dt: T) {
+        let limit = self.max_points.max(2);
+
+        for path in &mut self.pathlines {
+            if let Some(&head) = path.back()
+                && fluid.is_open(head.0, head.1)
+            {
+                let next = fluid.advect_point(head, dt);
+                if fluid.is_open(next.0, next.1) {
+                    path.push_back(next);
+                }
+            }
+            while path.len() > limit {
+                path.pop_front();
+            }
+        }
+
+        for (streak, &seed) in self.streaklines.iter_mut().zip(&self.seeds) {
+            for p in streak.iter_mut() {
+                if fluid.is_open(p.0, p.1) {
+                    *p = fluid.advect_point(*p, dt);
+                }
+            }
+            streak.push_front(seed);
+            streak.truncate(limit);
+        }
+    }
+
+    /// One pathline per seed, oldest point first.
+    pub fn pathlines(&self) -> impl Iterator<Item = &VecDeque<(T, T)>> {
+        self.pathlines.iter()
+    }
+
+    /// One streakline per seed, starting at the seed.
+    pub fn streaklines(&self) -> impl Iterator<Item = &VecDeque<(T, T)>> {
+        self.streaklines.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rk4_follows_a_circle() {
+        // solid body rotation keeps the radius; a full turn brings the point back
+        let turn = std::f64::consts::TAU;
+        let mut p = (1.0, 0.0);
+        for _ in 0..100 {
+            p = rk4(p, turn / 100.0, |x, y| (-y, x));
+        }
+        assert!((p.0 - 1.0).abs() < 1e-6 && p.1.abs() < 1e-6, "{p:?}");
+    }
+
+    #[test]
+    fn advect_point_moves_with_the_flow() {
+        let fluid = Fluid::uniform_for_tests(30, 20, 2.0, -1.0);
+        let p = fluid.advect_point((10.0, 10.0), 0.5);
+        assert!((p.0 - 11.0).abs() < 1e-12 && (p.1 - 9.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn streamline_is_evenly_spaced_and_stops_at_walls() {
+        let fluid = Fluid::uniform_for_tests(30, 20, 3.0, 0.0);
+        let line = fluid.streamline((5.0, 10.0), 0.5, 100);
+        assert!(line.windows(2).all(|w| ((w[1].0 - w[0].0) - 0.5).abs() < 1e-9 && w[1].1 == 10.0));
+        assert!(line.last().unwrap().0 <= 29.0);
+        assert!(line.len() < 100);
+
+        let upstream = fluid.streamline((5.0, 10.0), -0.5, 4);
+        assert_eq!(upstream.last(), Some(&(3.0, 10.0)));
+    }
+
+    #[test]
+    fn streamline_stops_at_obstacles_and_still_flow() {
+        let mut fluid = Fluid::uniform_for_tests(30, 20, 1.0, 0.0);
+        for j in 0..20 {
+            fluid.obstacles[(15, j)] = 1.0;
+        }
+        let line = fluid.streamline((5.0, 10.0), 1.0, 100);
+        assert!(line.iter().all(|p| p.0 < 14.6));
+
+        let still = Fluid::uniform_for_tests(30, 20, 0.0, 0.0);
+        assert_eq!(still.streamline((5.0, 10.0), 1.0, 100), [(5.0, 10.0)]);
+    }
+
+    #[test]
+    fn traces_in_steady_flow() {
+        let fluid = Fluid::uniform_for_tests(30, 20, 1.0, 0.0);
+        let mut traces = FlowTraces::new(4);
+        traces.add_seed((2.0, 5.0));
+        for _ in 0..6 {
+            traces.advance(&fluid, 1.0);
+        }
+
+        // newest points only, and both lie on the streamline through the seed
+        let path: Vec<_> = traces.pathlines().next().unwrap().iter().copied().collect();
+        assert_eq!(path.len(), 4);
+        assert!((path[3].0 - 8.0).abs() < 1e-9 && path.iter().all(|p| p.1 == 5.0));
+        let streak: Vec<_> = traces.streaklines().next().unwrap().iter().copied().collect();
+        assert_eq!(streak[0], (2.0, 5.0));
+        assert!((streak[3].0 - 5.0).abs() < 1e-9);
+
+        traces.restart();
+        assert_eq!(traces.pathlines().next().unwrap().len(), 1);
+        assert!(traces.streaklines().next().unwrap().is_empty());
+    }
+
+    #[test]
+    fn seed_grid_covers_the_fluid() {
+        let mut traces = FlowTraces::<f32>::new(10);
+        traces.seed_grid(30, 20, 10);
+        assert_eq!(traces.seeds(), [(5.0, 5.0), (15.0, 5.0), (25.0, 5.0), (5.0, 15.0), (15.0, 15.0), (25.0, 15.0)]);
+    }
+}
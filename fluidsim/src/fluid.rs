@@ -0,0 +1,606 @@
+use crate::color::Rgb;
+use crate::emitter::Emitter;
+use crate::field::Field2D;
+use crate::particles::ParticleSystem;
+use crate::real::Real;
+use crate::rng::stream_rng;
+use crate::scalar::{DYE_B, DYE_G, DYE_R, ScalarField, ScalarRegistry};
+
+/// Stable-fluids solver on a fixed `width * height` grid carrying any number of
+/// passive scalar fields (see [`ScalarRegistry`]).
+#[derive(Clone)]
+pub struct Fluid<T: Real = f32> {
+
+    /// Cells per row.
+    pub width: usize,
+    /// Number of rows.
+    pub height: usize,
+
+    /// Timestep used by [`crate::Simulation`] for each step.
+    pub time: T,
+    /// Velocity diffusion rate.
+    pub viscosity: T,
+    /// Number of equal solver steps each [`Fluid::step`] is split into.
+    pub substeps: u32,
+
+    /// Dye channels and any other passive scalars, each with its own diffusion and dissipation.
+    pub scalars: ScalarRegistry<T>,
+
+    /// Horizontal velocity component, in cells per unit time.
+    pub px: Field2D<T>,
+    /// Vertical velocity component; positive is down.
+    pub py: Field2D<T>,
+    /// Pressure from the last projection, as solved before scaling; read-only output.
+    pub pressure: Field2D<T>,
+    /// Solid cells, where the value is above one half. Velocity and scalars are held
+    /// at zero inside them; the projection and advection treat their faces as walls.
+    pub obstacles: Field2D<T>,
+
+    /// [`Fluid::add_emitter`] gives each emitter its own random stream; emitters pushed
+    /// here directly get one on the next step.
+    pub emitters: Vec<Emitter<T>>,
+    /// Tracers carried by the flow; random stream 0 is theirs.
+    pub particles: ParticleSystem<T>,
+
+    //what sinks draw in this substep, subtracted from the divergence the projection removes
+    inflow: Field2D<T>,
+
+    seed: u64,
+    pub(crate) next_stream: u64,
+}
+
+impl<T: Real> Fluid<T> {
+
+    /// Creates a 100x75 grid with red, green and blue dye channels using `diffusion`,
+    /// and the two default emitters facing each other.
+    pub fn new(time: T, diffusion: T, viscosity: T) -> Self {
+        Self::with_size(100, 75, time, diffusion, viscosity)
+    }
+
+    /// Like [`Fluid::new`] on a `width * height` grid, with the emitters placed at the
+    /// same relative positions.
+    pub fn with_size(width: usize, height: usize, time: T, diffusion: T, viscosity: T) -> Self {
+
+        let mut scalars = ScalarRegistry::new();
+        for (name, tint) in [(DYE_R, Rgb::new(255, 0, 0)), (DYE_G, Rgb::new(0, 255, 0)), (DYE_B, Rgb::new(0, 0, 255))] {
+            let dye = scalars.insert(ScalarField::new(name, width, height));
+            dye.diffusion = diffusion;
+            dye.dissipation = T::from_f64(0.99);
+            dye.tint = Some(tint);
+        }
+
+        let mut fluid = Self {
+
+            width,
+            height,
+            time,
+            viscosity,
+            substeps: 1,
+
+            scalars,
+
+            px: Field2D::new(width, height),
+            py: Field2D::new(width, height),
+            pressure: Field2D::new(width, height),
+            obstacles: Field2D::new(width, height),
+
+            emitters: Vec::new(),
+            particles: ParticleSystem::default(),
+
+            inflow: Field2D::new(width, height),
+
+            seed: 0,
+            next_stream: 1,
+        };
+
+        //initial emitter values
+
+        let mut e = Emitter::new(T::from_usize(width / 3), T::from_usize(height / 2));
+        e.name = String::from("Left Jet");
+        e.color = Rgb::new(255, 100, 100);
+        e.angle = T::ZERO;
+        fluid.add_emitter(e);
+
+        let mut e = Emitter::new(T::from_usize(2 * width / 3), T::from_usize(height / 2));
+        e.name = String::from("Right Jet");
+        e.color = Rgb::new(100, 100, 255);
+        e.angle = T::PI;
+        fluid.add_emitter(e);
+
+        fluid
+    }
+
+
+    /// Seed of the emitters' random streams, see [`Fluid::reseed`].
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Restarts every emitter's random stream from `seed`. Together with the scene,
+    /// the seed determines every following step bit-for-bit on the same build.
+    ///
+    /// ```
+    /// use fluidsim::Fluid;
+    ///
+    /// let run = || {
+    ///     let mut fluid = Fluid::<f32>::new(0.5, 0.0, 0.0);
+    ///     fluid.reseed(7);
+    ///     for _ in 0..5 {
+    ///         fluid.step(0.5);
+    ///     }
+    ///     fluid
+    /// };
+    /// assert_eq!(run().px, run().px);
+    /// ```
+    pub fn reseed(&mut self, seed: u64) {
+        self.adopt_emitters();
+        self.seed = seed;
+        self.particles.rng = stream_rng(seed, 0);
+        for emitter in &mut self.emitters {
+            emitter.rng = stream_rng(seed, emitter.stream);
+        }
+    }
+
+    /// Adds an emitter with a fresh random stream and returns its index.
+    pub fn add_emitter(&mut self, mut emitter: Emitter<T>) -> usize {
+        emitter.stream = self.next_stream;
+        emitter.rng = stream_rng(self.seed, emitter.stream);
+        self.next_stream += 1;
+
+        self.emitters.push(emitter);
+        self.emitters.len() - 1
+    }
+
+    //emitters pushed onto `emitters` by hand still have stream 0, which is the particles'
+
+    fn adopt_emitters(&mut self) {
+        for emitter in &mut self.emitters {
+            if emitter.stream == 0 {
+                emitter.stream = self.next_stream;
+                emitter.rng = stream_rng(self.seed, emitter.stream);
+                self.next_stream += 1;
+            }
+        }
+    }
+
+    /// Removes and returns the emitter at `index`, shifting later ones down.
+    pub fn remove_emitter(&mut self, index: usize) -> Emitter<T> {
+        self.emitters.remove(index)
+    }
+
+    /// Adds a copy of the emitter at `index` on its own random stream and returns the
+    /// copy's index. The copy keeps the original's clock, so its animation stays in phase.
+    pub fn duplicate_emitter(&mut self, index: usize) -> usize {
+        let mut copy = self.emitters[index].clone();
+        copy.name.push_str(" copy");
+        self.add_emitter(copy)
+    }
+
+    /// Index of the emitter with the given [`Emitter::id`].
+    pub fn emitter_index(&self, id: u64) -> Option<usize> {
+        self.emitters.iter().position(|e| e.id() == id)
+    }
+
+
+    /// Adds an empty scalar field sized to the grid (replacing one with the same name)
+    /// and returns it so its diffusion, dissipation and tint can be set.
+    pub fn add_scalar(&mut self, name: &str) -> &mut ScalarField<T> {
+        self.scalars.insert(ScalarField::new(name, self.width, self.height))
+    }
+
+    /// Pushes the fluid along a stroke from `from` to `to` (grid coordinates), as by a
+    /// finger dragged through it. Gaussian splats of `radius` cells are laid along the
+    /// stroke, scaled so the velocity added peaks near `velocity` however long or short
+    /// the stroke is; `dye` optionally adds color at the same peak `amount`. A stroke of
+    /// zero length adds nothing.
+    pub fn stir(&mut self, from: (T, T), to: (T, T), velocity: (T, T), radius: T, dye: Option<(Rgb, T)>) {
+        let radius = radius.max(T::from_f32(0.1));
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length <= T::ZERO {
+            return;
+        }
+
+        //splats half a radius apart, divided by their summed weight at the middle of the
+        //stroke; about sqrt(pi) * radius / spacing once the stroke is a few radii long
+
+        let count = (length / (radius * T::HALF)).to_usize() + 1;
+        let spacing = length / T::from_usize(count);
+        let coverage: T = (0..count).map(|k| {
+            let d = (T::from_usize(k) + T::HALF) * spacing - T::HALF * length;
+            (-(d * d) / (radius * radius)).exp()
+        }).sum();
+        let scale = T::ONE / coverage;
+
+        let dye: Vec<(usize, T)> = dye.map_or_else(Vec::new, |(color, amount)| {
+            let [r, g, b] = color.to_f32();
+            [(DYE_R, r), (DYE_G, g), (DYE_B, b)].into_iter()
+                .filter_map(|(name, c)| self.scalars.index_of(name).map(|idx| (idx, T::from_f32(c) * amount * scale)))
+                .collect()
+        });
+
+        for k in 0..count {
+            let t = (T::from_usize(k) + T::HALF) / T::from_usize(count);
+            let (x, y) = (from.0 + dx * t, from.1 + dy * t);
+            self.px.splat_gaussian(x, y, radius, velocity.0 * scale);
+            self.py.splat_gaussian(x, y, radius, velocity.1 * scale);
+            for &(idx, amount) in &dye {
+                self.scalars.fields[idx].field.splat_gaussian(x, y, radius, amount);
+            }
+        }
+    }
+
+
+    /// Marks (or with `solid` false, clears) every cell within `radius` of `(x, y)` as an obstacle.
+    pub fn paint_obstacle(&mut self, x: T, y: T, radius: T, solid: bool) {
+        let value = if solid { T::ONE } else { T::ZERO };
+        let reach = radius.to_isize() + 1;
+        let (cx, cy) = (x.floor().to_isize(), y.floor().to_isize());
+        for j in cy - reach..=cy + reach + 1 {
+            for i in cx - reach..=cx + reach + 1 {
+                if !self.obstacles.in_bounds(i, j) {
+                    continue;
+                }
+                let dx = T::from_f64(i as f64) - x;
+                let dy = T::from_f64(j as f64) - y;
+                if dx * dx + dy * dy <= radius * radius {
+                    self.obstacles[(i as usize, j as usize)] = value;
+                }
+            }
+        }
+    }
+
+
+    fn inject(&mut self, dt: T) {
+        self.inflow.fill(T::ZERO);
+        for emitter in &mut self.emitters {
+            emitter.inject(&mut self.scalars, &mut self.px, &mut self.py, dt);
+            emitter.add_inflow(&mut self.inflow);
+        }
+    }
+
+
+    /// Advances the simulation by `dt`, split into [`Fluid::substeps`] equal substeps.
+    pub fn step(&mut self, dt: T) {
+        self.adopt_emitters();
+        let n = self.substeps.max(1);
+        let sub_dt = dt / T::from_usize(n as usize);
+
+        for _ in 0..n {
+            self.substep(sub_dt);
+        }
+    }
+
+    //moves emitters, injects and moves particles (whose drag pushes back on the fluid),
+    //then diffuses, projects and advects velocity before diffusing, advecting and
+    //fading every scalar
+
+    fn substep(&mut self, dt: T) {
+
+        for emitter in &mut self.emitters {
+            emitter.update(dt);
+        }
+
+        self.inject(dt);
+        self.emit_particles(dt);
+        self.advance_particles(dt);
+
+        // Diffuse velocity (px, py)
+        let mut temp_x = self.px.clone();
+        let mut temp_y = self.py.clone();
+        self.diffuse(&mut temp_x, &self.px, self.viscosity, dt);
+        self.diffuse(&mut temp_y, &self.py, self.viscosity, dt);
+        self.px = temp_x;
+        self.py = temp_y;
+
+        self.project();
+
+        // Advect velocity
+        let vx0 = self.px.clone();
+        let vy0 = self.py.clone();
+        let mut new_px = self.px.clone();
+        let mut new_py = self.py.clone();
+        self.advect(&mut new_px, &vx0, &vx0, &vy0, dt);
+        self.advect(&mut new_py, &vy0, &vx0, &vy0, dt);
+        self.px = new_px;
+        self.py = new_py;
+
+        self.project();
+
+        // Diffuse, advect and fade passive scalars
+        let mut scalars = std::mem::take(&mut self.scalars);
+        for scalar in scalars.iter_mut() {
+            if scalar.diffusion > T::ZERO {
+                let mut diffused = scalar.field.clone();
+                self.diffuse(&mut diffused, &scalar.field, scalar.diffusion, dt);
+                scalar.field = diffused;
+            }
+
+            let mut advected = scalar.field.clone();
+            self.advect(&mut advected, &scalar.field, &self.px, &self.py, dt);
+
+            let keep = scalar.dissipation.max(T::ZERO).powf(dt);
+            let growth = scalar.growth * dt;
+            for (d, &solid) in advected.as_mut_slice().iter_mut().zip(self.obstacles.as_slice()) {
+                *d = if solid > T::HALF { T::ZERO } else { *d * keep + growth };
+            }
+            scalar.field = advected;
+        }
+        self.scalars = scalars;
+    }
+
+    /// Implicit diffusion of `x0` into `x` with Gauss-Seidel relaxation.
+    pub fn diffuse(&self, x: &mut Field2D<T>, x0: &Field2D<T>, diffusion: T, time: T) {
+        let a = time * diffusion * T::from_usize((self.width-2)*(self.height-2));
+        Self::linear_solver(x, x0, a, T::ONE+T::from_f32(6.0)*a);
+    }
+
+    fn linear_solver(x: &mut Field2D<T>, x0: &Field2D<T>, a: T, c: T) {
+        for _ in 0..20 {
+            for j in 1..x.height()-1 {
+                for i in 1..x.width()-1 {
+                    x[(i, j)] = (x0[(i, j)] +
+                        a*(x[(i+1, j)]+x[(i-1, j)]+x[(i, j+1)]+x[(i, j-1)])) / c;
+                }
+            }
+        }
+    }
+
+    /// Semi-Lagrangian advection of `d0` into `d` through the velocity `(vx, vy)`.
+    /// Backtraces stop short of obstacles, so nothing is carried through a wall.
+    pub fn advect(&self, d: &mut Field2D<T>, d0: &Field2D<T>, vx: &Field2D<T>, vy: &Field2D<T>, dt0: T) {
+        let has_solids = self.obstacles.as_slice().iter().any(|&s| s > T::HALF);
+
+        for j in 1..self.height-1 {
+            for i in 1..self.width-1 {
+                if has_solids && self.obstacles[(i, j)] > T::HALF {
+                    continue;
+                }
+
+                let mut x = T::from_usize(i) - dt0 * vx[(i, j)];
+                let mut y = T::from_usize(j) - dt0 * vy[(i, j)];
+
+                x = x.clamp(T::HALF, T::from_usize(self.width-1) - T::HALF);
+                y = y.clamp(T::HALF, T::from_usize(self.height-1) - T::HALF);
+
+                if has_solids {
+                    (x, y) = self.clamp_backtrace((T::from_usize(i), T::from_usize(j)), (x, y));
+                }
+
+                d[(i, j)] = d0.sample_bilinear(x, y);
+            }
+        }
+    }
+
+    //walks from `from` toward `to` half a cell at a time and stops at the last open
+    //point before a solid cell, so even one cell thick walls block the backtrace
+
+    fn clamp_backtrace(&self, from: (T, T), to: (T, T)) -> (T, T) {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let steps = ((dx.abs().max(dy.abs())) * T::from_f32(2.0)).to_usize() + 1;
+
+        let mut last = from;
+        for k in 1..=steps {
+            let t = T::from_usize(k) / T::from_usize(steps);
+            let p = (from.0 + dx * t, from.1 + dy * t);
+            if !self.is_open(p.0, p.1) {
+                return last;
+            }
+            last = p;
+        }
+        to
+    }
+
+    //makes the velocity divergence free (apart from sinks), with walls at obstacles
+
+    fn project(&mut self) {
+        let scale = T::from_usize(self.width);
+
+        // Solids don't move, so nothing flows through their faces
+        self.apply_obstacles_to_velocity();
+        let solid = |i: usize, j: usize| self.obstacles[(i, j)] > T::HALF;
+
+        // Compute divergence, less what sinks draw in so they keep it
+        let mut div = Field2D::divergence(&self.px, &self.py);
+        for (d, &inflow) in div.as_mut_slice().iter_mut().zip(self.inflow.as_slice()) {
+            *d = -(*d + inflow) / scale;
+        }
+
+        // Solve pressure. A solid neighbor mirrors the cell's own pressure (no flow
+        // across the face), so it drops out of the stencil
+        let mut p = Field2D::new(self.width, self.height);
+        for _ in 0..20 {
+            for j in 1..self.height-1 {
+                for i in 1..self.width-1 {
+                    if solid(i, j) {
+                        continue;
+                    }
+                    let mut sum = div[(i, j)];
+                    let mut open = 0;
+                    for (a, b) in [(i-1, j), (i+1, j), (i, j-1), (i, j+1)] {
+                        if !solid(a, b) {
+                            sum += p[(a, b)];
+                            open += 1;
+                        }
+                    }
+                    if open > 0 {
+                        p[(i, j)] = sum / T::from_usize(open);
+                    }
+                }
+            }
+        }
+
+        // Subtract gradient, mirrored at solid faces the same way
+        for j in 1..self.height-1 {
+            for i in 1..self.width-1 {
+                if solid(i, j) {
+                    continue;
+                }
+                let at = |a: usize, b: usize| if solid(a, b) { p[(i, j)] } else { p[(a, b)] };
+                let grad_x = T::HALF * (at(i+1, j) - at(i-1, j)) * T::from_usize(self.width);
+                let grad_y = T::HALF * (at(i, j+1) - at(i, j-1)) * T::from_usize(self.height);
+
+                self.px[(i, j)] -= grad_x;
+                self.py[(i, j)] -= grad_y;
+            }
+        }
+
+        self.pressure = p;
+    }
+
+    fn apply_obstacles_to_velocity(&mut self) {
+        let solid = self.obstacles.as_slice();
+        for ((u, v), &s) in self.px.as_mut_slice().iter_mut().zip(self.py.as_mut_slice()).zip(solid) {
+            if s > T::HALF {
+                *u = T::ZERO;
+                *v = T::ZERO;
+            }
+        }
+    }
+
+}
+
+#[cfg(test)]
+impl<T: Real> Fluid<T> {
+
+    /// Fluid without emitters moving at `(u, v)` everywhere; the fixture the tests share.
+    pub(crate) fn uniform_for_tests(width: usize, height: usize, u: T, v: T) -> Self {
+        let mut fluid = Self::with_size(width, height, T::HALF, T::ZERO, T::ZERO);
+        fluid.emitters.clear();
+        fluid.px.fill(u);
+        fluid.py.fill(v);
+        fluid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //still fluid with every dye channel at `level`
+    fn dyed(level: f64, substeps: u32) -> Fluid<f64> {
+        let mut fluid = Fluid::uniform_for_tests(16, 12, 0.0, 0.0);
+        fluid.substeps = substeps;
+        for scalar in fluid.scalars.iter_mut() {
+            scalar.field.fill(level);
+        }
+        fluid
+    }
+
+    #[test]
+    fn dissipation_does_not_depend_on_substeps() {
+        let (mut one, mut four) = (dyed(1.0, 1), dyed(1.0, 4));
+        for _ in 0..6 {
+            one.step(0.5);
+            four.step(0.5);
+        }
+        let (a, b) = (one.scalars.get(DYE_R).unwrap(), four.scalars.get(DYE_R).unwrap());
+        assert!((a.field[(8, 6)] - 0.99f64.powf(3.0)).abs() < 1e-9);
+        for (x, y) in a.field.as_slice().iter().zip(b.field.as_slice()) {
+            assert!((x - y).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn dissipation_does_not_depend_on_timestep() {
+        let (mut coarse, mut fine) = (dyed(2.0, 1), dyed(2.0, 1));
+        coarse.step(2.0);
+        for _ in 0..8 {
+            fine.step(0.25);
+        }
+        let (a, b) = (coarse.scalars.get(DYE_G).unwrap(), fine.scalars.get(DYE_G).unwrap());
+        assert!((a.field[(4, 4)] - b.field[(4, 4)]).abs() < 1e-9);
+    }
+
+    #[test]
+    fn growth_is_per_second() {
+        let mut fluid = dyed(0.0, 3);
+        fluid.add_scalar("age").growth = 1.0;
+        for _ in 0..4 {
+            fluid.step(0.5);
+        }
+        assert!((fluid.scalars.get("age").unwrap().field[(8, 6)] - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn advection_does_not_cross_thin_walls() {
+        let mut fluid = Fluid::uniform_for_tests(16, 12, 0.0, 0.0);
+        for j in 0..fluid.height {
+            fluid.obstacles[(8, j)] = 1.0;
+        }
+        let mut dye = Field2D::new(fluid.width, fluid.height);
+        for j in 0..fluid.height {
+            for i in 0..8 {
+                dye[(i, j)] = 1.0;
+            }
+        }
+        let vx = Field2D::filled(fluid.width, fluid.height, 3.0);
+        let vy = Field2D::new(fluid.width, fluid.height);
+
+        let mut out = dye.clone();
+        fluid.advect(&mut out, &dye, &vx, &vy, 1.0);
+        for j in 1..fluid.height - 1 {
+            assert_eq!(out[(9, j)], 0.0);
+            assert_eq!(out[(10, j)], 0.0);
+            assert_eq!(out[(6, j)], 1.0);
+        }
+    }
+
+    #[test]
+    fn projection_treats_obstacles_as_walls() {
+        let mut fluid = Fluid::uniform_for_tests(16, 12, 0.0, 0.0);
+        for j in 4..8 {
+            for i in 6..10 {
+                fluid.obstacles[(i, j)] = 1.0;
+            }
+        }
+        fluid.px.fill(1.0);
+        fluid.project();
+
+        // solids are still, and the flow next to them no longer runs into them
+        assert_eq!(fluid.px[(7, 5)], 0.0);
+        // a uniform stream stopped dead at the block diverges by 0.5 beside it
+        let before = 0.5;
+        let div = Field2D::divergence(&fluid.px, &fluid.py);
+        for (i, j) in [(5, 5), (5, 6), (10, 5), (10, 6)] {
+            assert!(div[(i, j)].abs() < before * 0.5, "divergence {} at ({i}, {j})", div[(i, j)]);
+        }
+        assert!(fluid.px[(5, 5)] < 1.0 && fluid.px[(5, 5)] >= 0.0);
+    }
+
+    fn peak(field: &Field2D<f64>) -> f64 {
+        field.as_slice().iter().fold(0.0, |m, v| m.max(v.abs()))
+    }
+
+    #[test]
+    fn stir_peaks_near_the_requested_velocity_for_any_length() {
+        let strokes = [((6.0, 6.0), (9.0, 6.0)), ((1.0, 6.0), (15.0, 6.0)), ((3.0, 2.0), (13.0, 10.0))];
+        for (from, to) in strokes {
+            let mut fluid = Fluid::uniform_for_tests(16, 12, 0.0, 0.0);
+            fluid.stir(from, to, (2.0, -1.0), 2.0, None);
+            let (u, v) = (peak(&fluid.px), peak(&fluid.py));
+            assert!((u - 2.0).abs() < 0.2 && (v - 1.0).abs() < 0.1, "{from:?} to {to:?}: {u}, {v}");
+        }
+    }
+
+    #[test]
+    fn zero_length_stir_adds_nothing() {
+        let mut fluid = Fluid::uniform_for_tests(16, 12, 0.0, 0.0);
+        fluid.stir((8.0, 6.0), (8.0, 6.0), (5.0, 5.0), 2.0, Some((Rgb::WHITE, 1.0)));
+        fluid.stir((8.0, 6.0), (8.0, 6.0), (5.0, 5.0), 0.0, None);
+        assert_eq!(peak(&fluid.px) + peak(&fluid.py), 0.0);
+        assert!(fluid.scalars.iter().all(|s| peak(&s.field) == 0.0));
+    }
+
+    #[test]
+    fn stir_adds_dye_only_when_given_a_color() {
+        let mut fluid = Fluid::uniform_for_tests(16, 12, 0.0, 0.0);
+        fluid.stir((4.0, 6.0), (12.0, 6.0), (1.0, 0.0), 1.5, None);
+        assert!(fluid.scalars.iter().all(|s| peak(&s.field) == 0.0));
+
+        fluid.stir((4.0, 6.0), (12.0, 6.0), (1.0, 0.0), 1.5, Some((Rgb::new(255, 0, 0), 0.5)));
+        assert!((peak(&fluid.scalars.get(DYE_R).unwrap().field) - 0.5).abs() < 0.05);
+        assert_eq!(peak(&fluid.scalars.get(DYE_G).unwrap().field), 0.0);
+        assert_eq!(peak(&fluid.scalars.get(DYE_B).unwrap().field), 0.0);
+    }
+}
@@ -0,0 +1,55 @@
+//! Grid based 2D fluid solver (stable fluids) with moving, rotating emitters.
+//!
+//! The crate has no GUI dependency; the `wp` viewer is one frontend for it.
+//! Everything is generic over [`Real`], so `Fluid<f64>` can be used for validation
+//! runs while `Fluid<f32>` (the default) drives the interactive viewer.
+//! Enable the `egui` feature to convert [`Rgb`] to and from `egui::Color32`.
+//!
+//! ```
+//! use fluidsim::{Fluid, Simulation};
+//!
+//! let mut sim = Simulation::new(Fluid::<f64>::new(0.5, 0.0, 0.0));
+//! sim.step_n(10);
+//! assert_eq!(sim.steps, 10);
+//! assert!(sim.fluid.diagnostics().total_dye > 0.0);
+//! ```
+
+#![warn(missing_docs)]
+
+mod animation;
+mod color;
+mod colormap;
+mod controller;
+mod diagnostics;
+mod emitter;
+mod field;
+mod flowlines;
+mod fluid;
+mod lic;
+mod motion;
+mod palette;
+mod particles;
+mod real;
+mod render;
+mod rng;
+mod scalar;
+mod shape;
+mod tonemap;
+
+pub use animation::{Animatable, EmitterAnimation, Interpolation, Keyframe, Pulse, Schedule, Track};
+pub use color::Rgb;
+pub use colormap::Colormap;
+pub use controller::Simulation;
+pub use diagnostics::Diagnostics;
+pub use emitter::{Emitter, EmitterKind, ScalarSource};
+pub use field::Field2D;
+pub use flowlines::FlowTraces;
+pub use fluid::Fluid;
+pub use motion::{Motion, MotionPath, RandomWalk};
+pub use palette::{ColorMode, Gradient, GradientStop};
+pub use particles::{Inertia, Integrator, Particle, ParticleColor, ParticleSystem};
+pub use real::Real;
+pub use render::{DisplayField, Image, LicColoring, LicSettings, ValueRange, Visualization};
+pub use scalar::{DYE_B, DYE_G, DYE_R, ScalarField, ScalarRegistry};
+pub use shape::EmitterShape;
+pub use tonemap::{Bloom, ToneMapping, ToneOperator};
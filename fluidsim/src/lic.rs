@@ -0,0 +1,119 @@
+use crate::field::Field2D;
+use crate::fluid::Fluid;
+use crate::real::Real;
+
+//distance between samples along each streamline, in cells
+const STEP: f32 = 0.5;
+
+impl<T: Real> Fluid<T> {
+
+    /// Line integral convolution: every cell averages a fixed white-noise texture along
+    /// `length` cells of streamline in each direction, so the noise smears into streaks
+    /// that follow the flow. Contrast is stretched to roughly `0..=1`, with still and
+    /// solid regions left as plain noise.
+    pub fn lic(&self, length: T) -> Field2D<T> {
+        self.lic_with_noise(length, &lic_noise(self.width * self.height))
+    }
+
+    //the convolution over a texture from `lic_noise`, which renderers keep between frames
+
+    pub(crate) fn lic_with_noise(&self, length: T, noise: &[f32]) -> Field2D<T> {
+        let (w, h) = (self.width, self.height);
+        let steps = (length / T::from_f32(STEP)).to_usize().max(1);
+        let step = T::from_f32(STEP);
+        let still = T::from_f32(1e-4);
+
+        let mut out = Field2D::new(w, h);
+        for j in 0..h {
+            for i in 0..w {
+                let mut sum = T::from_f32(noise[j * w + i]);
+                let mut count = 1usize;
+
+                for dir in [T::ONE, -T::ONE] {
+                    let (mut x, mut y) = (T::from_usize(i), T::from_usize(j));
+                    for _ in 0..steps {
+                        let (u, v) = self.velocity_at(x, y);
+                        let speed = (u * u + v * v).sqrt();
+                        if speed < still {
+                            break;
+                        }
+                        x += dir * step * u / speed;
+                        y += dir * step * v / speed;
+                        if !self.is_open(x, y) {
+                            break;
+                        }
+                        sum += T::from_f32(noise[(y + T::HALF).to_usize() * w + (x + T::HALF).to_usize()]);
+                        count += 1;
+                    }
+                }
+
+                //averaging n samples shrinks the spread by sqrt(n); undo most of that
+
+                let mean = sum / T::from_usize(count);
+                let stretch = T::from_usize(count).sqrt() * T::from_f32(0.7);
+                out[(i, j)] = (T::HALF + (mean - T::HALF) * stretch).clamp(T::ZERO, T::ONE);
+            }
+        }
+        out
+    }
+}
+
+//white noise in 0..1 for `len` cells; each value is a hash of the cell index, so the
+//texture doesn't flicker between frames
+
+pub(crate) fn lic_noise(len: usize) -> Vec<f32> {
+    (0..len as u64).map(noise).collect()
+}
+
+fn noise(i: u64) -> f32 {
+    let mut z = i.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 40) as f32 / (1u64 << 24) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fluid::Fluid;
+    use crate::render::{DisplayField, Visualization};
+
+    //mean squared difference between each interior cell and its neighbor `(dx, dy)` away
+    fn roughness(f: &crate::Field2D<f32>, dx: usize, dy: usize) -> f32 {
+        let mut sum = 0.0;
+        for j in 5..25 {
+            for i in 5..35 {
+                sum += (f[(i + dx, j + dy)] - f[(i, j)]).powi(2);
+            }
+        }
+        sum
+    }
+
+    #[test]
+    fn streaks_follow_the_flow() {
+        let lic = Fluid::uniform_for_tests(40, 30, 1.0, 0.0).lic(10.0);
+        assert!(lic.as_slice().iter().all(|v| (0.0..=1.0).contains(v)));
+        assert!(roughness(&lic, 1, 0) * 4.0 < roughness(&lic, 0, 1));
+
+        let lic = Fluid::uniform_for_tests(40, 30, 0.0, 2.0).lic(10.0);
+        assert!(roughness(&lic, 0, 1) * 4.0 < roughness(&lic, 1, 0));
+    }
+
+    #[test]
+    fn still_flow_is_plain_noise() {
+        let still = Fluid::uniform_for_tests(40, 30, 0.0, 0.0);
+        let lic = still.lic(10.0);
+        assert_eq!(lic, still.lic(10.0));
+        let noise = super::lic_noise(40 * 30);
+        assert!((lic[(7, 3)] - (0.5 + (noise[3 * 40 + 7] - 0.5) * 0.7)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn field_values_use_the_streak_length() {
+        let fluid = Fluid::uniform_for_tests(40, 30, 1.0, 0.5);
+        let mut vis = Visualization::new(DisplayField::Lic);
+        vis.lic.length = 25.0;
+        assert_eq!(fluid.field_values(&vis), Some(fluid.lic(25.0)));
+        assert_ne!(fluid.field_values(&vis), Some(fluid.lic(10.0)));
+    }
+}
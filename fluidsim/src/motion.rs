@@ -0,0 +1,386 @@
+use rand::Rng;
+
+use crate::field::catmull_rom;
+use crate::real::Real;
+use crate::rng::{SimRng, stream_rng};
+
+/// How an emitter moves by itself, evaluated on the emitter's own clock.
+#[derive(Clone, Debug, Default)]
+pub enum Motion<T: Real = f32> {
+    /// Stays where it is put (or where its keyframes put it).
+    #[default]
+    Static,
+    /// Follows a route, see [`MotionPath`].
+    Path(Box<MotionPath<T>>),
+    /// Circles `center` at `angular_speed` radians per second.
+    Orbit {
+        /// Point circled.
+        center: (T, T),
+        /// Distance from `center`, in cells.
+        radius: T,
+        /// Radians per second; positive is clockwise on screen.
+        angular_speed: T,
+        /// Starting angle, in radians.
+        phase: T,
+    },
+    /// `center + amplitude * sin(TAU * frequency * t + phase)` per axis, frequencies in Hz.
+    Lissajous {
+        /// Middle of the figure.
+        center: (T, T),
+        /// Largest offset from `center` along each axis, in cells.
+        amplitude: (T, T),
+        /// Oscillations per second along each axis.
+        frequency: (T, T),
+        /// Added to the x argument, in radians.
+        phase: T,
+    },
+    /// Wanders around, see [`RandomWalk`].
+    RandomWalk(Box<RandomWalk<T>>),
+}
+
+/// Route travelled by [`Motion::Path`] at `speed` cells per second, through a Catmull-Rom
+/// spline instead of straight segments when `spline` is set. Closed and repeating when
+/// `looped`, otherwise stops at the last point. Needs at least two points to move.
+///
+/// The distance covered is accumulated step by step, so changing `speed` changes how
+/// fast the emitter goes from where it is rather than making it jump.
+#[derive(Clone, Debug)]
+pub struct MotionPath<T: Real = f32> {
+    /// Route through these points, in grid coordinates.
+    pub points: Vec<(T, T)>,
+    /// Smooths the route through the points.
+    pub spline: bool,
+    /// Cells per second.
+    pub speed: T,
+    /// Joins the last point back to the first and keeps going.
+    pub looped: bool,
+    travelled: T,
+    cache: Option<PathCache<T>>,
+}
+
+//polyline and segment lengths for the route they were built from
+
+#[derive(Clone, Debug)]
+struct PathCache<T: Real> {
+    points: Vec<(T, T)>,
+    spline: bool,
+    looped: bool,
+    line: Vec<(T, T)>,
+    lengths: Vec<T>,
+    total: T,
+}
+
+impl<T: Real> MotionPath<T> {
+
+    /// Route from the start of `points`.
+    pub fn new(points: Vec<(T, T)>, spline: bool, speed: T, looped: bool) -> Self {
+        Self { points, spline, speed, looped, travelled: T::ZERO, cache: None }
+    }
+
+    /// Distance along the route covered so far, wrapped to one lap when looped.
+    pub fn travelled(&self) -> T {
+        self.travelled
+    }
+
+    /// Polyline the emitter follows, in grid coordinates.
+    pub fn polyline(&self) -> Vec<(T, T)> {
+        match &self.cache {
+            Some(cache) if cache.matches(self) => cache.line.clone(),
+            _ => path_polyline(&self.points, self.spline, self.looped),
+        }
+    }
+
+    fn advance(&mut self, dt: T) -> Option<(T, T)> {
+        if self.points.len() < 2 {
+            return None;
+        }
+        if !self.cache.as_ref().is_some_and(|c| c.matches(self)) {
+            let line = path_polyline(&self.points, self.spline, self.looped);
+            let lengths = segment_lengths(&line);
+            let total = lengths.iter().copied().sum();
+            self.cache = Some(PathCache { points: self.points.clone(), spline: self.spline, looped: self.looped, line, lengths, total });
+        }
+        let cache = self.cache.as_ref()?;
+
+        self.travelled += self.speed * dt;
+        self.travelled = if self.looped && cache.total > T::ZERO {
+            let laps = self.travelled / cache.total;
+            (laps - laps.floor()) * cache.total
+        } else {
+            self.travelled.clamp(T::ZERO, cache.total)
+        };
+        sample_polyline(&cache.line, &cache.lengths, self.travelled)
+    }
+}
+
+impl<T: Real> PathCache<T> {
+    fn matches(&self, path: &MotionPath<T>) -> bool {
+        self.spline == path.spline && self.looped == path.looped && self.points == path.points
+    }
+}
+
+/// Seeded wander around a center, turning randomly and bouncing back inside `range`.
+#[derive(Clone, Debug)]
+pub struct RandomWalk<T: Real = f32> {
+    /// Middle of the area the walk stays in.
+    pub center: (T, T),
+    /// Largest distance from `center`, in cells.
+    pub range: T,
+    /// Cells per second.
+    pub speed: T,
+    /// Largest heading change, in radians per second.
+    pub turn_rate: T,
+    seed: u64,
+    heading: T,
+    offset: (T, T),
+    rng: SimRng,
+}
+
+impl<T: Real> RandomWalk<T> {
+
+    /// Walk starting at `center`, heading along +x.
+    pub fn new(center: (T, T), range: T, speed: T, turn_rate: T, seed: u64) -> Self {
+        Self { center, range, speed, turn_rate, seed, heading: T::ZERO, offset: (T::ZERO, T::ZERO), rng: stream_rng(seed, 0) }
+    }
+
+    /// Seed of the walk's random stream.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Restarts the walk from the center with a new seed.
+    pub fn reseed(&mut self, seed: u64) {
+        *self = Self::new(self.center, self.range, self.speed, self.turn_rate, seed);
+    }
+
+    fn advance(&mut self, dt: T) -> (T, T) {
+        let turn = T::from_f64(self.rng.r#gen::<f64>()) - T::HALF;
+        self.heading += turn * (self.turn_rate + self.turn_rate) * dt;
+
+        self.offset.0 += self.heading.cos() * self.speed * dt;
+        self.offset.1 += self.heading.sin() * self.speed * dt;
+
+        let dist = (self.offset.0 * self.offset.0 + self.offset.1 * self.offset.1).sqrt();
+        if dist > self.range && dist > T::ZERO {
+            let pull = self.range / dist;
+            self.offset = (self.offset.0 * pull, self.offset.1 * pull);
+            self.heading += T::PI;
+        }
+
+        (self.center.0 + self.offset.0, self.center.1 + self.offset.1)
+    }
+}
+
+impl<T: Real> Motion<T> {
+
+    /// Position at emitter time `time`, after a step of `dt`; `None` for [`Motion::Static`].
+    pub fn advance(&mut self, time: T, dt: T) -> Option<(T, T)> {
+        match self {
+            Motion::Static => None,
+            Motion::Path(path) => path.advance(dt),
+            Motion::Orbit { center, radius, angular_speed, phase } => {
+                let a = *phase + *angular_speed * time;
+                Some((center.0 + *radius * a.cos(), center.1 + *radius * a.sin()))
+            }
+            Motion::Lissajous { center, amplitude, frequency, phase } => {
+                let x = (T::TAU * frequency.0 * time + *phase).sin();
+                let y = (T::TAU * frequency.1 * time).sin();
+                Some((center.0 + amplitude.0 * x, center.1 + amplitude.1 * y))
+            }
+            Motion::RandomWalk(walk) => Some(walk.advance(dt)),
+        }
+    }
+
+    /// Polyline, in grid coordinates, showing where the motion goes; empty for
+    /// [`Motion::Static`].
+    pub fn overlay(&self) -> Vec<(T, T)> {
+        match self {
+            Motion::Static => Vec::new(),
+            Motion::Path(path) => path.polyline(),
+            Motion::Orbit { center, radius, .. } => circle(*center, *radius),
+            Motion::RandomWalk(walk) => circle(walk.center, walk.range),
+            Motion::Lissajous { center, amplitude, frequency, phase } => {
+                // one second covers a whole number of periods only for integer
+                // frequencies, so trace a few seconds to close typical curves
+                let samples = 400;
+                let span = T::from_f32(4.0);
+                (0..=samples).map(|i| {
+                    let t = span * T::from_usize(i) / T::from_usize(samples);
+                    let x = (T::TAU * frequency.0 * t + *phase).sin();
+                    let y = (T::TAU * frequency.1 * t).sin();
+                    (center.0 + amplitude.0 * x, center.1 + amplitude.1 * y)
+                }).collect()
+            }
+        }
+    }
+}
+
+fn circle<T: Real>(center: (T, T), radius: T) -> Vec<(T, T)> {
+    (0..=48).map(|i| {
+        let a = T::TAU * T::from_usize(i) / T::from_usize(48);
+        (center.0 + radius * a.cos(), center.1 + radius * a.sin())
+    }).collect()
+}
+
+//the path as straight segments: the points themselves, or a finely sampled spline through them
+
+fn path_polyline<T: Real>(points: &[(T, T)], spline: bool, looped: bool) -> Vec<(T, T)> {
+    let mut line = points.to_vec();
+    if points.len() < 2 {
+        return line;
+    }
+    if looped {
+        line.push(points[0]);
+    }
+    if !spline {
+        return line;
+    }
+
+    let n = points.len() as isize;
+    let at = |i: isize| if looped { points[i.rem_euclid(n) as usize] } else { points[i.clamp(0, n - 1) as usize] };
+    let segments = if looped { n } else { n - 1 };
+    let steps = 16;
+
+    let mut smooth = Vec::with_capacity((segments * steps + 1) as usize);
+    for seg in 0..segments {
+        let (p0, p1, p2, p3) = (at(seg - 1), at(seg), at(seg + 1), at(seg + 2));
+        for s in 0..steps {
+            let t = T::from_usize(s as usize) / T::from_usize(steps as usize);
+            smooth.push((catmull_rom(p0.0, p1.0, p2.0, p3.0, t), catmull_rom(p0.1, p1.1, p2.1, p3.1, t)));
+        }
+    }
+    smooth.push(if looped { points[0] } else { points[points.len() - 1] });
+    smooth
+}
+
+fn segment_lengths<T: Real>(line: &[(T, T)]) -> Vec<T> {
+    line.windows(2).map(|w| {
+        let (dx, dy) = (w[1].0 - w[0].0, w[1].1 - w[0].1);
+        (dx * dx + dy * dy).sqrt()
+    }).collect()
+}
+
+//point `distance` along the polyline with the given segment lengths, clamped to its ends
+
+fn sample_polyline<T: Real>(line: &[(T, T)], lengths: &[T], distance: T) -> Option<(T, T)> {
+    let first = *line.first()?;
+    let mut d = distance.max(T::ZERO);
+    if d == T::ZERO {
+        return Some(first);
+    }
+
+    for (w, &len) in line.windows(2).zip(lengths) {
+        if d <= len && len > T::ZERO {
+            let t = d / len;
+            return Some((w[0].0 + (w[1].0 - w[0].0) * t, w[0].1 + (w[1].1 - w[0].1) * t));
+        }
+        d -= len;
+    }
+    line.last().copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: (f64, f64), b: (f64, f64)) -> bool {
+        (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9
+    }
+
+    fn square(looped: bool, speed: f64) -> Motion<f64> {
+        Motion::Path(Box::new(MotionPath::new(vec![(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)], false, speed, looped)))
+    }
+
+    #[test]
+    fn sample_polyline_walks_the_segments() {
+        let line = [(0.0, 0.0), (4.0, 0.0), (4.0, 2.0)];
+        let lengths = segment_lengths(&line);
+        assert_eq!(lengths, [4.0, 2.0]);
+        assert!(close(sample_polyline(&line, &lengths, 0.0).unwrap(), (0.0, 0.0)));
+        assert!(close(sample_polyline(&line, &lengths, 3.0).unwrap(), (3.0, 0.0)));
+        assert!(close(sample_polyline(&line, &lengths, 5.0).unwrap(), (4.0, 1.0)));
+        assert!(close(sample_polyline(&line, &lengths, 9.0).unwrap(), (4.0, 2.0)));
+        assert_eq!(sample_polyline::<f64>(&[], &[], 1.0), None);
+    }
+
+    #[test]
+    fn open_path_stops_at_the_end() {
+        let mut motion = square(false, 2.0);
+        assert!(close(motion.advance(1.0, 1.0).unwrap(), (2.0, 0.0)));
+        assert!(close(motion.advance(2.0, 1.0).unwrap(), (4.0, 0.0)));
+        for _ in 0..10 {
+            motion.advance(0.0, 1.0);
+        }
+        assert!(close(motion.advance(0.0, 1.0).unwrap(), (0.0, 4.0)));
+    }
+
+    #[test]
+    fn looped_path_wraps_around() {
+        let mut motion = square(true, 3.0);
+        // one lap is 16 cells
+        for _ in 0..5 {
+            motion.advance(0.0, 1.0);
+        }
+        assert!(close(motion.advance(0.0, 1.0).unwrap(), (2.0, 0.0)));
+        if let Motion::Path(path) = &motion {
+            assert!((path.travelled() - 2.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn changing_speed_does_not_jump() {
+        let mut motion = square(false, 1.0);
+        motion.advance(0.0, 1.0);
+        let before = motion.advance(0.0, 1.0).unwrap();
+        if let Motion::Path(path) = &mut motion {
+            path.speed = 0.5;
+        }
+        let after = motion.advance(0.0, 1.0).unwrap();
+        assert!(close(before, (2.0, 0.0)));
+        assert!(close(after, (2.5, 0.0)));
+    }
+
+    #[test]
+    fn editing_the_route_rebuilds_it() {
+        let mut motion = square(false, 1.0);
+        motion.advance(0.0, 1.0);
+        if let Motion::Path(path) = &mut motion {
+            path.points = vec![(0.0, 0.0), (0.0, 10.0)];
+        }
+        assert!(close(motion.advance(0.0, 1.0).unwrap(), (0.0, 2.0)));
+        assert_eq!(motion.overlay(), [(0.0, 0.0), (0.0, 10.0)]);
+    }
+
+    #[test]
+    fn spline_passes_through_its_points() {
+        let points = vec![(0.0, 0.0), (5.0, 2.0), (8.0, 8.0)];
+        let line = path_polyline(&points, true, false);
+        for p in &points {
+            assert!(line.iter().any(|q| close(*p, *q)));
+        }
+        assert!(line.len() > points.len());
+        assert_eq!(line.last(), points.last());
+    }
+
+    #[test]
+    fn periodic_motions() {
+        let mut orbit = Motion::Orbit { center: (10.0, 10.0), radius: 3.0, angular_speed: std::f64::consts::FRAC_PI_2, phase: 0.0 };
+        assert!(close(orbit.advance(1.0, 1.0).unwrap(), (10.0, 13.0)));
+
+        let mut curve = Motion::Lissajous { center: (0.0, 0.0), amplitude: (2.0, 3.0), frequency: (0.25, 0.5), phase: 0.0 };
+        assert!(close(curve.advance(1.0, 1.0).unwrap(), (2.0, 0.0)));
+
+        assert_eq!(Motion::<f64>::Static.advance(1.0, 1.0), None);
+    }
+
+    #[test]
+    fn random_walk_stays_in_range_and_repeats() {
+        let walk = || Motion::RandomWalk(Box::new(RandomWalk::<f64>::new((20.0, 20.0), 5.0, 8.0, 4.0, 9)));
+        let (mut a, mut b) = (walk(), walk());
+        for _ in 0..200 {
+            let p = a.advance(0.0, 0.1).unwrap();
+            assert_eq!(Some(p), b.advance(0.0, 0.1));
+            assert!(((p.0 - 20.0).powi(2) + (p.1 - 20.0).powi(2)).sqrt() <= 5.0 + 1e-9);
+        }
+    }
+}
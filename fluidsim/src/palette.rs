@@ -0,0 +1,115 @@
+use crate::animation::Animatable;
+use crate::color::Rgb;
+use crate::real::Real;
+
+/// One color of a [`Gradient`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GradientStop<T: Real = f32> {
+    /// Position along the gradient, in `0..=1`.
+    pub position: T,
+    /// Color at `position`.
+    pub color: Rgb,
+}
+
+/// Multi-stop color gradient, linearly blended between stops.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient<T: Real = f32> {
+    /// Kept sorted by position by [`Gradient::sort`]; unsorted stops still sample
+    /// but in list order.
+    pub stops: Vec<GradientStop<T>>,
+}
+
+impl<T: Real> Default for Gradient<T> {
+    fn default() -> Self {
+        Self::new(&[(T::ZERO, Rgb::new(255, 60, 60)), (T::HALF, Rgb::new(255, 220, 60)), (T::ONE, Rgb::new(60, 120, 255))])
+    }
+}
+
+impl<T: Real> Gradient<T> {
+
+    /// Gradient through `(position, color)` stops, sorted by position.
+    pub fn new(stops: &[(T, Rgb)]) -> Self {
+        let mut gradient = Self { stops: stops.iter().map(|&(position, color)| GradientStop { position, color }).collect() };
+        gradient.sort();
+        gradient
+    }
+
+    /// Sorts the stops by position, e.g. after editing them.
+    pub fn sort(&mut self) {
+        self.stops.sort_by(|a, b| a.position.partial_cmp(&b.position).unwrap_or(std::cmp::Ordering::Equal));
+    }
+
+    /// Color at `t`, holding the end colors outside the stops; black when there are none.
+    pub fn sample(&self, t: T) -> Rgb {
+        let Some(first) = self.stops.first() else {
+            return Rgb::BLACK;
+        };
+        if t <= first.position {
+            return first.color;
+        }
+        for pair in self.stops.windows(2) {
+            let (a, b) = (&pair[0], &pair[1]);
+            if t < b.position {
+                let span = b.position - a.position;
+                let f = if span > T::ZERO { (t - a.position) / span } else { T::ONE };
+                return a.color.lerp(b.color, f);
+            }
+        }
+        self.stops[self.stops.len() - 1].color
+    }
+}
+
+/// Where an emitter's dye color comes from over time.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub enum ColorMode<T: Real = f32> {
+    /// The emitter's `color`, possibly keyframed.
+    #[default]
+    Solid,
+    /// Rotates the hue at `speed` turns per second.
+    HueCycle {
+        /// Hue turns per second.
+        speed: T,
+        /// In `0..=1`.
+        saturation: T,
+        /// In `0..=1`.
+        value: T,
+    },
+    /// Runs through the gradient once every `period` seconds, then starts over.
+    Gradient {
+        /// Colors run through.
+        gradient: Gradient<T>,
+        /// Seconds per pass; 0 holds the first color.
+        period: T,
+    },
+    /// Picks a new random hue at the start of every pulse of the emitter's
+    /// [`crate::Pulse`]; a constant pulse keeps the first pick.
+    RandomPerPulse {
+        /// In `0..=1`.
+        saturation: T,
+        /// In `0..=1`.
+        value: T,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gradient_hits_stops_and_blends_between() {
+        let (red, green, blue) = (Rgb::new(255, 0, 0), Rgb::new(0, 255, 0), Rgb::new(0, 0, 255));
+        let gradient = Gradient::<f32>::new(&[(1.0, blue), (0.0, red), (0.5, green)]);
+
+        assert_eq!(gradient.sample(0.0), red);
+        assert_eq!(gradient.sample(0.5), green);
+        assert_eq!(gradient.sample(1.0), blue);
+
+        assert_eq!(gradient.sample(0.25), Rgb::new(128, 128, 0));
+        assert_eq!(gradient.sample(0.75), Rgb::new(0, 128, 128));
+
+        // the end colors hold outside the stops
+        assert_eq!(gradient.sample(-1.0), red);
+        assert_eq!(gradient.sample(2.0), blue);
+        assert_eq!(Gradient::<f32> { stops: Vec::new() }.sample(0.5), Rgb::BLACK);
+    }
+}
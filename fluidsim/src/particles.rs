@@ -0,0 +1,510 @@
+use std::collections::VecDeque;
+
+use rand::Rng;
+
+use crate::color::Rgb;
+use crate::flowlines::rk4;
+use crate::fluid::Fluid;
+use crate::real::Real;
+use crate::rng::{SimRng, stream_rng};
+
+/// Scheme used to move particles through the velocity field each substep.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Integrator {
+    /// Midpoint method; half the velocity lookups of RK4.
+    Rk2,
+    /// Classic fourth-order Runge-Kutta.
+    #[default]
+    Rk4,
+}
+
+/// Where a newly released particle takes its color from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ParticleColor {
+    /// The dye under it at release.
+    #[default]
+    Dye,
+    /// The releasing emitter's color; particles seeded by hand are white.
+    Emitter,
+}
+
+/// Mass, size and drag of an inertial [`Particle`], for sand, rain and spray. Drag
+/// follows Stokes' law, a force of `drag * size` times the velocity relative to the
+/// fluid, so particles catch up with the flow over a response time of
+/// `mass / (drag * size)` seconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Inertia<T: Real = f32> {
+    /// Mass of one particle; heavier particles respond more slowly.
+    pub mass: T,
+    /// Radius in cells; particles keep this far from the walls.
+    pub size: T,
+    /// Drag coefficient per unit of size.
+    pub drag: T,
+    /// Acceleration in cells per second squared; positive y is down.
+    pub gravity: (T, T),
+    /// Fraction of the normal speed kept when bouncing off a wall or obstacle.
+    pub restitution: T,
+    /// Pushes the drag's reaction back into the fluid velocity, so heavy particles
+    /// drag the fluid along with them.
+    pub two_way: bool,
+}
+
+impl<T: Real> Default for Inertia<T> {
+    fn default() -> Self {
+        Self {
+            mass: T::ONE,
+            size: T::HALF,
+            drag: T::from_f32(4.0),
+            gravity: (T::ZERO, T::from_f32(2.0)),
+            restitution: T::HALF,
+            two_way: false,
+        }
+    }
+}
+
+impl<T: Real> Inertia<T> {
+
+    /// Seconds for a particle's velocity to close most of the gap to the fluid's.
+    pub fn response_time(&self) -> T {
+        self.mass / (self.drag * self.size).max(T::from_f32(1e-6))
+    }
+}
+
+/// A particle carried by the flow; massless unless it has [`Inertia`].
+#[derive(Clone, Debug)]
+pub struct Particle<T: Real = f32> {
+    /// Horizontal position, in grid coordinates.
+    pub x: T,
+    /// Vertical position, in grid coordinates.
+    pub y: T,
+    /// Only used by inertial particles; tracers move at the fluid velocity.
+    pub vx: T,
+    /// Only used by inertial particles.
+    pub vy: T,
+    /// Simulated time since release.
+    pub age: T,
+    /// Color drawn with.
+    pub color: Rgb,
+    /// Recent positions, oldest first, ending at the current one.
+    pub trail: VecDeque<(T, T)>,
+    /// Taken from [`ParticleSystem::inertia`] at release; `None` for a massless tracer.
+    pub inertia: Option<Inertia<T>>,
+}
+
+/// Particles owned by a [`Fluid`] and advanced with it, massless tracers unless they were
+/// released while [`ParticleSystem::inertia`] was set. Emitters release them at
+/// [`crate::Emitter::particle_rate`]; [`ParticleSystem::spawn`] adds them by hand.
+#[derive(Clone, Debug)]
+pub struct ParticleSystem<T: Real = f32> {
+    /// Scheme tracers are moved with.
+    pub integrator: Integrator,
+    /// Particles older than this many seconds are removed.
+    pub lifetime: T,
+    /// Positions kept per particle for drawing trails; 1 keeps only the current one.
+    pub trail_length: usize,
+    /// Where new particles take their color from.
+    pub color: ParticleColor,
+    /// Releases stop while this many particles are alive.
+    pub max_particles: usize,
+    /// Given to every particle released from now on; `None` releases massless tracers.
+    /// Particles already alive keep what they were released with, so both kinds mix.
+    pub inertia: Option<Inertia<T>>,
+
+    particles: Vec<Particle<T>>,
+    pub(crate) rng: SimRng,
+}
+
+impl<T: Real> Default for ParticleSystem<T> {
+    fn default() -> Self {
+        Self {
+            integrator: Integrator::Rk4,
+            lifetime: T::from_f32(20.0),
+            trail_length: 12,
+            color: ParticleColor::Dye,
+            max_particles: 5000,
+            inertia: None,
+            particles: Vec::new(),
+            rng: stream_rng(0, 0),
+        }
+    }
+}
+
+impl<T: Real> ParticleSystem<T> {
+
+    /// Live particles, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &Particle<T>> {
+        self.particles.iter()
+    }
+
+    /// Number of live particles.
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    /// Whether no particles are alive.
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    /// Removes every particle.
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    /// Releases a particle at `(x, y)` moving at `velocity` unless the system is full.
+    pub fn spawn(&mut self, x: T, y: T, velocity: (T, T), color: Rgb) {
+        if self.particles.len() >= self.max_particles {
+            return;
+        }
+        let (vx, vy) = velocity;
+        let trail = VecDeque::from([(x, y)]);
+        self.particles.push(Particle { x, y, vx, vy, age: T::ZERO, color, trail, inertia: self.inertia });
+    }
+
+    //ages, moves and culls every particle for one substep; returns the momentum
+    //inertial particles hand back to the fluid as (x, y, du, dv)
+
+    fn advance(&mut self, fluid: &Fluid<T>, dt: T) -> Vec<(T, T, T, T)> {
+        let mut reactions = Vec::new();
+        if dt <= T::ZERO {
+            return reactions;
+        }
+        let (lifetime, trail_length, integrator) = (self.lifetime, self.trail_length.max(1), self.integrator);
+
+        self.particles.retain_mut(|p| {
+            p.age += dt;
+            if let Some(inertia) = p.inertia {
+                let (du, dv) = inertia.step(p, fluid, dt);
+                if inertia.two_way {
+                    reactions.push((p.x, p.y, du, dv));
+                }
+            } else {
+                let (x, y) = match integrator {
+                    Integrator::Rk2 => {
+                        let (u, v) = fluid.velocity_at(p.x, p.y);
+                        let (mx, my) = (p.x + T::HALF * dt * u, p.y + T::HALF * dt * v);
+                        let (u, v) = fluid.velocity_at(mx, my);
+                        (p.x + dt * u, p.y + dt * v)
+                    }
+                    Integrator::Rk4 => rk4((p.x, p.y), dt, |x, y| fluid.velocity_at(x, y)),
+                };
+                p.vx = (x - p.x) / dt;
+                p.vy = (y - p.y) / dt;
+                p.x = x;
+                p.y = y;
+            }
+            p.trail.push_back((p.x, p.y));
+            while p.trail.len() > trail_length {
+                p.trail.pop_front();
+            }
+            p.age <= lifetime && fluid.is_open(p.x, p.y)
+        });
+        reactions
+    }
+}
+
+impl<T: Real> Inertia<T> {
+
+    //moves one particle under drag and gravity, bouncing off walls and obstacles;
+    //returns the velocity change the drag's reaction gives the fluid cell
+
+    fn step(&self, p: &mut Particle<T>, fluid: &Fluid<T>, dt: T) -> (T, T) {
+        let tau = self.response_time();
+        let (u, v) = fluid.velocity_at(p.x, p.y);
+
+        //exact solution of dv/dt = (u - v) / tau + g over dt, stable for any tau
+
+        let decay = (-dt / tau).exp();
+        let (tx, ty) = (u + self.gravity.0 * tau, v + self.gravity.1 * tau);
+        let vx = tx + (p.vx - tx) * decay;
+        let vy = ty + (p.vy - ty) * decay;
+
+        //drag impulse is the change not explained by gravity; the fluid gets the opposite
+
+        let reaction = (
+            -self.mass * (vx - p.vx - self.gravity.0 * dt),
+            -self.mass * (vy - p.vy - self.gravity.1 * dt),
+        );
+        p.vx = vx;
+        p.vy = vy;
+
+        //one axis at a time, so a blocked axis bounces while the other slides; particles
+        //too big for the grid are held in the middle of it
+
+        let max = (T::from_usize(fluid.width - 1), T::from_usize(fluid.height - 1));
+        let size = self.size.max(T::ZERO);
+        let lo = (size.min(max.0 * T::HALF), size.min(max.1 * T::HALF));
+        let hi = (max.0 - lo.0, max.1 - lo.1);
+
+        let x = p.x + p.vx * dt;
+        if x < lo.0 || x > hi.0 || !fluid.is_open(x, p.y) {
+            p.vx = -p.vx * self.restitution;
+            p.x = p.x.clamp(lo.0, hi.0);
+        } else {
+            p.x = x;
+        }
+
+        let y = p.y + p.vy * dt;
+        if y < lo.1 || y > hi.1 || !fluid.is_open(p.x, y) {
+            p.vy = -p.vy * self.restitution;
+            p.y = p.y.clamp(lo.1, hi.1);
+        } else {
+            p.y = y;
+        }
+
+        reaction
+    }
+}
+
+impl<T: Real> Fluid<T> {
+
+    //color a released particle takes when it inherits the dye
+
+    fn dye_color_at(&self, x: T, y: T) -> Rgb {
+        let i = (x + T::HALF).to_usize().min(self.width - 1);
+        let j = (y + T::HALF).to_usize().min(self.height - 1);
+        let [r, g, b] = self.scalars.tinted_color(i, j);
+        Rgb::from_f32(r.to_f32(), g.to_f32(), b.to_f32())
+    }
+
+    /// Releases a particle at `(x, y)` colored by the dye there, or white when particles
+    /// take emitter colors. Particles start at the local fluid velocity.
+    pub fn seed_particle(&mut self, x: T, y: T) {
+        let color = match self.particles.color {
+            ParticleColor::Dye => self.dye_color_at(x, y),
+            ParticleColor::Emitter => Rgb::WHITE,
+        };
+        self.particles.spawn(x, y, self.velocity_at(x, y), color);
+    }
+
+    //each emitter releases its rate times its current level, carrying fractions over
+
+    pub(crate) fn emit_particles(&mut self, dt: T) {
+        let mut released = Vec::new();
+        for emitter in &mut self.emitters {
+            let level = emitter.strength * emitter.activity();
+            emitter.particle_carry += emitter.particle_rate * level.max(T::ZERO) * dt;
+            let radius = emitter.shape.radius();
+            while emitter.particle_carry >= T::ONE {
+                emitter.particle_carry -= T::ONE;
+
+                //uniform over the disk the shape fits in
+
+                let r = radius * T::from_f32(self.particles.rng.r#gen::<f32>().sqrt());
+                let a = T::TAU * T::from_f32(self.particles.rng.r#gen::<f32>());
+                released.push((emitter.x + r * a.cos(), emitter.y + r * a.sin(), emitter.color));
+            }
+        }
+
+        for (x, y, emitter_color) in released {
+            if !self.is_open(x, y) {
+                continue;
+            }
+            let color = match self.particles.color {
+                ParticleColor::Dye => self.dye_color_at(x, y),
+                ParticleColor::Emitter => emitter_color,
+            };
+            self.particles.spawn(x, y, self.velocity_at(x, y), color);
+        }
+    }
+
+    //moves the particles through the current velocity; two-way reactions land in px/py
+    //alongside the emitters' forces, before the projection makes the field divergence free
+
+    pub(crate) fn advance_particles(&mut self, dt: T) {
+        let mut particles = std::mem::take(&mut self.particles);
+        let reactions = particles.advance(self, dt);
+        self.particles = particles;
+
+        //each cell holds unit fluid mass, so momentum is a velocity change
+
+        for (x, y, du, dv) in reactions {
+            self.px.splat_bilinear(x, y, du);
+            self.py.splat_bilinear(x, y, dv);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emitter::Emitter;
+
+    //solid body rotation about the grid center; linear, so bilinear sampling is exact
+    fn rotating() -> Fluid<f64> {
+        let mut fluid = Fluid::uniform_for_tests(40, 40, 0.0, 0.0);
+        for j in 0..40 {
+            for i in 0..40 {
+                fluid.px[(i, j)] = -(j as f64 - 20.0);
+                fluid.py[(i, j)] = i as f64 - 20.0;
+            }
+        }
+        fluid
+    }
+
+    //distance from the center after a quarter turn in `n` steps
+    fn radius_after_quarter_turn(integrator: Integrator, n: usize) -> f64 {
+        let fluid = rotating();
+        let mut system = ParticleSystem { integrator, ..ParticleSystem::default() };
+        system.spawn(30.0, 20.0, (0.0, 0.0), Rgb::WHITE);
+        for _ in 0..n {
+            system.advance(&fluid, std::f64::consts::FRAC_PI_2 / n as f64);
+        }
+        let p = system.iter().next().unwrap();
+        ((p.x - 20.0).powi(2) + (p.y - 20.0).powi(2)).sqrt()
+    }
+
+    #[test]
+    fn both_integrators_are_exact_in_uniform_flow() {
+        for integrator in [Integrator::Rk2, Integrator::Rk4] {
+            let fluid = Fluid::uniform_for_tests(40, 40, 2.0, 1.0);
+            let mut system = ParticleSystem { integrator, trail_length: 3, ..ParticleSystem::default() };
+            system.spawn(5.0, 5.0, (0.0, 0.0), Rgb::WHITE);
+            for _ in 0..4 {
+                system.advance(&fluid, 0.5);
+            }
+            let p = system.iter().next().unwrap();
+            assert!((p.x - 9.0).abs() < 1e-12 && (p.y - 7.0).abs() < 1e-12);
+            assert!((p.vx - 2.0).abs() < 1e-12 && (p.vy - 1.0).abs() < 1e-12);
+            assert_eq!(p.trail.len(), 3);
+            assert_eq!(p.trail.back(), Some(&(p.x, p.y)));
+        }
+    }
+
+    #[test]
+    fn rk4_keeps_orbits_closer_than_rk2() {
+        let rk2 = (radius_after_quarter_turn(Integrator::Rk2, 8) - 10.0).abs();
+        let rk4 = (radius_after_quarter_turn(Integrator::Rk4, 8) - 10.0).abs();
+        assert!(rk4 < 1e-3, "{rk4}");
+        assert!(rk4 * 100.0 < rk2, "rk2 {rk2}, rk4 {rk4}");
+    }
+
+    #[test]
+    fn particles_die_of_age_or_leaving_the_grid() {
+        let fluid = Fluid::uniform_for_tests(40, 40, 4.0, 0.0);
+        let mut system = ParticleSystem { lifetime: 1.0, ..ParticleSystem::default() };
+        system.spawn(37.5, 10.0, (0.0, 0.0), Rgb::WHITE);
+        system.spawn(5.0, 10.0, (0.0, 0.0), Rgb::WHITE);
+        system.advance(&fluid, 0.5);
+        assert_eq!(system.len(), 1, "the first left the grid");
+        system.advance(&fluid, 0.5);
+        assert_eq!(system.len(), 1);
+        system.advance(&fluid, 0.5);
+        assert!(system.is_empty());
+    }
+
+    #[test]
+    fn emitters_release_at_their_rate() {
+        let mut fluid = Fluid::<f64>::uniform_for_tests(40, 40, 0.0, 0.0);
+        let mut emitter = Emitter::new(20.0, 20.0);
+        emitter.particle_rate = 3.0;
+        emitter.color = Rgb::new(10, 20, 30);
+        fluid.add_emitter(emitter);
+        fluid.particles.color = ParticleColor::Emitter;
+
+        // 3 per second over 2.5 seconds, fractions carried between steps
+        for _ in 0..5 {
+            fluid.emit_particles(0.5);
+        }
+        assert_eq!(fluid.particles.len(), 7);
+        assert!(fluid.particles.iter().all(|p| p.color == Rgb::new(10, 20, 30)));
+        assert!(fluid.particles.iter().all(|p| ((p.x - 20.0).powi(2) + (p.y - 20.0).powi(2)).sqrt() <= 1.5));
+
+        fluid.particles.max_particles = 8;
+        for _ in 0..10 {
+            fluid.emit_particles(0.5);
+        }
+        assert_eq!(fluid.particles.len(), 8);
+    }
+
+    #[test]
+    fn inertial_particles_reach_terminal_velocity() {
+        let fluid = Fluid::uniform_for_tests(40, 40, 1.5, -0.5);
+        let inertia = Inertia { mass: 2.0, size: 0.5, drag: 8.0, gravity: (0.0, 3.0), ..Inertia::default() };
+        let tau = inertia.response_time();
+        assert_eq!(tau, 0.5);
+
+        let mut p = Particle { x: 5.0, y: 5.0, vx: 0.0, vy: 0.0, age: 0.0, color: Rgb::WHITE, trail: VecDeque::new(), inertia: None };
+        for _ in 0..40 {
+            inertia.step(&mut p, &fluid, 0.1);
+        }
+        // fluid velocity plus g * tau
+        assert!((p.vx - 1.5).abs() < 1e-3, "{}", p.vx);
+        assert!((p.vy - (-0.5 + 3.0 * tau)).abs() < 1e-3, "{}", p.vy);
+    }
+
+    #[test]
+    fn inertial_particles_bounce_off_walls() {
+        let fluid = Fluid::uniform_for_tests(40, 40, 0.0, 0.0);
+        let inertia = Inertia { gravity: (0.0, 50.0), restitution: 0.5, ..Inertia::default() };
+        let mut p = Particle { x: 20.0, y: 38.0, vx: 0.0, vy: 4.0, age: 0.0, color: Rgb::WHITE, trail: VecDeque::new(), inertia: None };
+        inertia.step(&mut p, &fluid, 0.5);
+        assert!(p.vy < 0.0);
+        assert!(p.y <= 39.0 - inertia.size);
+    }
+
+    #[test]
+    fn oversized_particles_stay_mid_grid() {
+        let mut fluid = Fluid::<f32>::with_size(4, 4, 0.5, 0.0, 0.0);
+        fluid.particles.inertia = Some(Inertia { size: 3.0, ..Inertia::default() });
+        fluid.seed_particle(1.0, 1.0);
+        fluid.step(0.5);
+        let p = fluid.particles.iter().next().unwrap();
+        assert_eq!((p.x, p.y), (1.5, 1.5));
+    }
+
+    #[test]
+    fn inertial_particles_in_obstacles_are_removed() {
+        let mut fluid = Fluid::uniform_for_tests(40, 40, 0.0, 0.0);
+        fluid.obstacles[(10, 10)] = 1.0;
+        fluid.particles.inertia = Some(Inertia::default());
+        fluid.seed_particle(10.0, 10.0);
+        fluid.seed_particle(20.0, 20.0);
+        fluid.step(0.1);
+        assert_eq!(fluid.particles.len(), 1);
+    }
+
+    #[test]
+    fn two_way_coupling_leaves_the_field_divergence_free() {
+        let mut fluid = Fluid::uniform_for_tests(40, 40, 0.0, 0.0);
+        fluid.particles.inertia = Some(Inertia { mass: 5.0, two_way: true, ..Inertia::default() });
+        for k in 0..20 {
+            fluid.particles.spawn(15.0 + k as f64 * 0.5, 20.0, (0.0, 6.0), Rgb::WHITE);
+        }
+
+        // the bare impulses are strongly divergent; the projection has to see them
+        let mut unprojected = fluid.clone();
+        unprojected.advance_particles(0.5);
+        let raw = unprojected.diagnostics().max_divergence;
+
+        fluid.step(0.5);
+        let d = fluid.diagnostics();
+        assert!(d.max_speed > 0.0, "particles pushed the fluid");
+        assert!(d.max_divergence < raw * 0.2, "{} left of {raw}", d.max_divergence);
+    }
+
+    #[test]
+    fn tracers_and_inertial_particles_mix() {
+        let mut fluid = Fluid::uniform_for_tests(40, 40, 2.0, 0.0);
+        fluid.particles.spawn(10.0, 10.0, (2.0, 0.0), Rgb::WHITE);
+        fluid.particles.inertia = Some(Inertia { gravity: (0.0, 4.0), ..Inertia::default() });
+        fluid.particles.spawn(10.0, 20.0, (2.0, 0.0), Rgb::WHITE);
+        fluid.particles.inertia = None;
+
+        fluid.advance_particles(0.5);
+        let [tracer, heavy] = [0, 1].map(|i| fluid.particles.iter().nth(i).unwrap().clone());
+        assert!(tracer.inertia.is_none() && heavy.inertia.is_some());
+        assert!((tracer.x - 11.0).abs() < 1e-9 && tracer.y == 10.0);
+        assert!(heavy.y > 20.0, "gravity pulls the inertial one down");
+    }
+
+    #[test]
+    fn zero_timestep_leaves_particles_alone() {
+        let mut fluid = Fluid::uniform_for_tests(40, 40, 1.0, 1.0);
+        fluid.seed_particle(5.0, 5.0);
+        fluid.advance_particles(0.0);
+        let p = fluid.particles.iter().next().unwrap();
+        assert_eq!((p.x, p.y, p.vx, p.vy, p.age), (5.0, 5.0, 1.0, 1.0, 0.0));
+        assert_eq!(p.trail.len(), 1);
+    }
+}
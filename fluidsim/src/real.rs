@@ -0,0 +1,119 @@
+use std::fmt::Debug;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// Floating-point type the solver is generic over, implemented for `f32` and `f64`.
+///
+/// `f32` is what the interactive viewer uses; `f64` is meant for validation runs
+/// where accumulated rounding error matters more than speed.
+pub trait Real:
+    Copy
+    + Debug
+    + Default
+    + PartialOrd
+    + Send
+    + Sync
+    + 'static
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign
+    + Sum
+{
+    /// 0.
+    const ZERO: Self;
+    /// 1.
+    const ONE: Self;
+    /// 0.5.
+    const HALF: Self;
+    /// π.
+    const PI: Self;
+    /// 2π.
+    const TAU: Self;
+
+    /// Converts from `f32`.
+    fn from_f32(v: f32) -> Self;
+    /// Converts from `f64`.
+    fn from_f64(v: f64) -> Self;
+    /// Converts from `usize`.
+    fn from_usize(v: usize) -> Self;
+
+    /// Converts to `f32`, rounding if needed.
+    fn to_f32(self) -> f32;
+    /// Converts to `f64`.
+    fn to_f64(self) -> f64;
+    /// Truncating conversion; negative values and NaN become 0.
+    fn to_usize(self) -> usize;
+    /// Truncating conversion; NaN becomes 0.
+    fn to_isize(self) -> isize;
+
+    /// Largest integer not above `self`.
+    fn floor(self) -> Self;
+    /// Square root.
+    fn sqrt(self) -> Self;
+    /// Absolute value.
+    fn abs(self) -> Self;
+    /// `e^self`.
+    fn exp(self) -> Self;
+    /// `self^n`.
+    fn powf(self, n: Self) -> Self;
+    /// Sine, in radians.
+    fn sin(self) -> Self;
+    /// Cosine, in radians.
+    fn cos(self) -> Self;
+    /// Four-quadrant arctangent of `self / other`.
+    fn atan2(self, other: Self) -> Self;
+    /// Smaller of the two.
+    fn min(self, other: Self) -> Self;
+    /// Larger of the two.
+    fn max(self, other: Self) -> Self;
+    /// `self` limited to `min..=max`.
+    fn clamp(self, min: Self, max: Self) -> Self;
+    /// Radians to degrees.
+    fn to_degrees(self) -> Self;
+    /// Degrees to radians.
+    fn to_radians(self) -> Self;
+}
+
+macro_rules! impl_real {
+    ($t:ident) => {
+        impl Real for $t {
+            const ZERO: Self = 0.0;
+            const ONE: Self = 1.0;
+            const HALF: Self = 0.5;
+            const PI: Self = std::$t::consts::PI;
+            const TAU: Self = std::$t::consts::TAU;
+
+            fn from_f32(v: f32) -> Self { v as $t }
+            fn from_f64(v: f64) -> Self { v as $t }
+            fn from_usize(v: usize) -> Self { v as $t }
+
+            fn to_f32(self) -> f32 { self as f32 }
+            fn to_f64(self) -> f64 { self as f64 }
+            fn to_usize(self) -> usize { self as usize }
+            fn to_isize(self) -> isize { self as isize }
+
+            fn floor(self) -> Self { $t::floor(self) }
+            fn sqrt(self) -> Self { $t::sqrt(self) }
+            fn abs(self) -> Self { $t::abs(self) }
+            fn exp(self) -> Self { $t::exp(self) }
+            fn powf(self, n: Self) -> Self { $t::powf(self, n) }
+            fn sin(self) -> Self { $t::sin(self) }
+            fn cos(self) -> Self { $t::cos(self) }
+            fn atan2(self, other: Self) -> Self { $t::atan2(self, other) }
+            fn min(self, other: Self) -> Self { $t::min(self, other) }
+            fn max(self, other: Self) -> Self { $t::max(self, other) }
+            fn clamp(self, min: Self, max: Self) -> Self { $t::clamp(self, min, max) }
+            fn to_degrees(self) -> Self { $t::to_degrees(self) }
+            fn to_radians(self) -> Self { $t::to_radians(self) }
+        }
+    };
+}
+
+impl_real!(f32);
+impl_real!(f64);
@@ -0,0 +1,494 @@
+use std::io::{self, Write};
+
+use crate::color::Rgb;
+use crate::colormap::Colormap;
+use crate::field::Field2D;
+use crate::fluid::Fluid;
+use crate::lic::lic_noise;
+use crate::real::Real;
+use crate::tonemap::ToneMapping;
+
+const OBSTACLE_GRAY: Rgb = Rgb::new(90, 90, 90);
+
+/// Quantity drawn by [`Fluid::render`].
+#[derive(Clone, Debug, PartialEq, Default)]
+pub enum DisplayField {
+    /// Tinted scalar fields in their own colors; ignores the colormap.
+    #[default]
+    Dye,
+    /// Length of the velocity.
+    Speed,
+    /// Horizontal velocity.
+    VelocityX,
+    /// Vertical velocity.
+    VelocityY,
+    /// From the last projection, see [`Fluid::pressure`].
+    Pressure,
+    /// Curl of the velocity.
+    Vorticity,
+    /// Divergence of the velocity; near zero after projection.
+    Divergence,
+    /// Any scalar field by name, e.g. temperature.
+    Scalar(String),
+    /// 1 in solid cells, 0 elsewhere.
+    Obstacles,
+    /// Line integral convolution streaks, see [`Fluid::lic`] and [`LicSettings`].
+    Lic,
+}
+
+impl DisplayField {
+
+    /// Every field except [`DisplayField::Scalar`], which needs a name.
+    pub const BUILT_IN: [DisplayField; 9] = [
+        DisplayField::Dye,
+        DisplayField::Speed,
+        DisplayField::VelocityX,
+        DisplayField::VelocityY,
+        DisplayField::Pressure,
+        DisplayField::Vorticity,
+        DisplayField::Divergence,
+        DisplayField::Obstacles,
+        DisplayField::Lic,
+    ];
+
+    /// Label shown in pickers.
+    pub fn name(&self) -> &str {
+        match self {
+            DisplayField::Dye => "Dye",
+            DisplayField::Speed => "Velocity Magnitude",
+            DisplayField::VelocityX => "Velocity X",
+            DisplayField::VelocityY => "Velocity Y",
+            DisplayField::Pressure => "Pressure",
+            DisplayField::Vorticity => "Vorticity",
+            DisplayField::Divergence => "Divergence",
+            DisplayField::Scalar(name) => name,
+            DisplayField::Obstacles => "Obstacle Mask",
+            DisplayField::Lic => "Line Integral Convolution",
+        }
+    }
+
+    /// Whether values are centered on zero, so a diverging map over a symmetric range reads best.
+    pub fn is_signed(&self) -> bool {
+        matches!(self, DisplayField::VelocityX | DisplayField::VelocityY | DisplayField::Pressure | DisplayField::Vorticity | DisplayField::Divergence)
+    }
+}
+
+/// Values mapped onto the two ends of the colormap.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum ValueRange<T: Real = f32> {
+    /// The field's own minimum and maximum every frame, widened to include zero.
+    #[default]
+    Auto,
+    /// `-m..=m` where `m` is the largest magnitude, so zero sits mid-map.
+    Symmetric,
+    /// Fixed bounds.
+    Fixed {
+        /// Value at the low end.
+        min: T,
+        /// Value at the high end.
+        max: T,
+    },
+}
+
+/// What colors the streaks of [`DisplayField::Lic`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum LicColoring {
+    /// Streak brightness through the colormap.
+    #[default]
+    Plain,
+    /// Speed through the colormap, with the streaks as brightness.
+    Speed,
+    /// The dye colors, with the streaks as brightness.
+    Dye,
+}
+
+/// Settings for [`DisplayField::Lic`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LicSettings<T: Real = f32> {
+    /// Cells of streamline averaged each way; longer gives smoother, longer streaks.
+    pub length: T,
+    /// What the streaks are colored by.
+    pub coloring: LicColoring,
+}
+
+impl<T: Real> Default for LicSettings<T> {
+    fn default() -> Self {
+        Self { length: T::from_f32(10.0), coloring: LicColoring::Plain }
+    }
+}
+
+/// How [`Fluid::render`] turns the fluid state into colors.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Visualization<T: Real = f32> {
+    /// Quantity drawn.
+    pub field: DisplayField,
+    /// Values mapped onto the ends of the colormap.
+    pub range: ValueRange<T>,
+    /// Spans `0..=1` from the low to the high end of the range.
+    pub colormap: Colormap<T>,
+    /// Runs the colormap from high to low instead.
+    pub reversed: bool,
+    /// Only used by [`DisplayField::Lic`].
+    pub lic: LicSettings<T>,
+    /// Applied wherever dye is drawn, including dye-colored LIC.
+    pub tone: ToneMapping<T>,
+}
+
+impl<T: Real> Default for Visualization<T> {
+    fn default() -> Self {
+        Self::new(DisplayField::Dye)
+    }
+}
+
+impl<T: Real> Visualization<T> {
+
+    /// Shows `field` with coolwarm over a symmetric range if it is signed, otherwise viridis over its extent.
+    pub fn new(field: DisplayField) -> Self {
+        let (range, colormap) = Self::defaults(&field);
+        Self { field, range, colormap, reversed: false, lic: LicSettings::default(), tone: ToneMapping::default() }
+    }
+
+    /// Switches to `field` with its default range. The colormap follows the field's
+    /// default unless one was picked by hand; `reversed`, LIC and tone settings are kept.
+    pub fn set_field(&mut self, field: DisplayField) {
+        let (range, colormap) = Self::defaults(&field);
+        if self.colormap == Self::defaults(&self.field).1 {
+            self.colormap = colormap;
+        }
+        self.range = range;
+        self.field = field;
+    }
+
+    fn defaults(field: &DisplayField) -> (ValueRange<T>, Colormap<T>) {
+        match field {
+            _ if field.is_signed() => (ValueRange::Symmetric, Colormap::Coolwarm),
+            DisplayField::Lic => (ValueRange::Auto, Colormap::Grayscale),
+            _ => (ValueRange::Auto, Colormap::Viridis),
+        }
+    }
+
+    /// Colormap color at `t` in `0..=1`, honoring `reversed`; for legends.
+    pub fn color(&self, t: T) -> Rgb {
+        self.colormap.sample(if self.reversed { T::ONE - t } else { t })
+    }
+}
+
+/// Row-major RGB image, one pixel per grid cell. Reuse one across frames; rendering
+/// resizes it only when the grid size changes, and keeps its working buffers.
+#[derive(Clone, Debug, Default)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+    scratch: Scratch,
+}
+
+//buffers rendering needs besides the pixels, kept from frame to frame
+
+#[derive(Clone, Debug, Default)]
+struct Scratch {
+    lic_noise: Vec<f32>,
+    //linear dye colors before tone mapping, and the bloom layer
+    hdr: Vec<[f32; 3]>,
+    glow: Vec<[f32; 3]>,
+}
+
+impl Scratch {
+
+    //LIC input texture for `len` cells, regenerated only when the grid size changes
+
+    fn noise(&mut self, len: usize) -> &[f32] {
+        if self.lic_noise.len() != len {
+            self.lic_noise = lic_noise(len);
+        }
+        &self.lic_noise
+    }
+}
+
+impl PartialEq for Image {
+    fn eq(&self, other: &Self) -> bool {
+        self.width == other.width && self.height == other.height && self.pixels == other.pixels
+    }
+}
+
+impl Image {
+
+    /// Black image.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height, pixels: vec![Rgb::BLACK; width * height], scratch: Scratch::default() }
+    }
+
+    /// Pixels per row.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of rows.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Pixels in row-major order.
+    pub fn pixels(&self) -> &[Rgb] {
+        &self.pixels
+    }
+
+    /// Pixels in row-major order, mutably.
+    pub fn pixels_mut(&mut self) -> &mut [Rgb] {
+        &mut self.pixels
+    }
+
+    /// Writes a binary PPM (P6), which most image viewers and converters read.
+    pub fn write_ppm<W: Write>(&self, mut out: W) -> io::Result<()> {
+        write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+        let bytes: Vec<u8> = self.pixels.iter().flat_map(|&c| <[u8; 3]>::from(c)).collect();
+        out.write_all(&bytes)?;
+        out.flush()
+    }
+
+    /// Resizes to `width * height`, keeping the allocation when the size is unchanged.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.pixels.resize(width * height, Rgb::BLACK);
+    }
+}
+
+impl<T: Real> Fluid<T> {
+
+    /// Per cell values of `vis.field`, LIC with `vis.lic.length`, as the colormap sees
+    /// them; `None` for [`DisplayField::Dye`] and unknown scalars.
+    pub fn field_values(&self, vis: &Visualization<T>) -> Option<Field2D<T>> {
+        self.values(&vis.field, vis.lic.length)
+    }
+
+    fn values(&self, field: &DisplayField, lic_length: T) -> Option<Field2D<T>> {
+        let values = match field {
+            DisplayField::Dye => return None,
+            DisplayField::Lic => self.lic(lic_length),
+            DisplayField::Speed => {
+                let speeds = self.px.as_slice().iter().zip(self.py.as_slice()).map(|(&u, &v)| (u * u + v * v).sqrt());
+                Field2D::from_vec(self.width, self.height, speeds.collect())
+            }
+            DisplayField::VelocityX => self.px.clone(),
+            DisplayField::VelocityY => self.py.clone(),
+            DisplayField::Pressure => self.pressure.clone(),
+            DisplayField::Vorticity => Field2D::curl(&self.px, &self.py),
+            DisplayField::Divergence => Field2D::divergence(&self.px, &self.py),
+            DisplayField::Scalar(name) => self.scalars.get(name)?.field.clone(),
+            DisplayField::Obstacles => self.obstacles.clone(),
+        };
+        Some(values)
+    }
+
+    /// Draws `vis` into `image` and returns the value range the colormap spans, or
+    /// `None` when no legend applies: dye, plain or dye-colored LIC, and scalars that
+    /// do not exist (drawn black).
+    pub fn render(&self, vis: &Visualization<T>, image: &mut Image) -> Option<(T, T)> {
+        match vis.field {
+            DisplayField::Dye => {
+                self.render_dye(&vis.tone, image);
+                return None;
+            }
+            DisplayField::Lic => return self.render_lic(vis, image),
+            _ => {}
+        }
+
+        image.resize(self.width, self.height);
+        let Some(values) = self.field_values(vis) else {
+            image.pixels.fill(Rgb::BLACK);
+            return None;
+        };
+
+        let (min, max) = value_range(&values, vis.range);
+        let span = if max > min { max - min } else { T::ONE };
+
+        for (pixel, &v) in image.pixels.iter_mut().zip(values.as_slice()) {
+            *pixel = vis.color(((v - min) / span).clamp(T::ZERO, T::ONE));
+        }
+        Some((min, max))
+    }
+
+    fn render_lic(&self, vis: &Visualization<T>, image: &mut Image) -> Option<(T, T)> {
+        let streaks = self.lic_with_noise(vis.lic.length, image.scratch.noise(self.width * self.height));
+
+        //streaks average one half, so doubling them keeps the mean brightness
+
+        let shade = |c: Rgb, s: T| {
+            let s = (s + s).to_f32();
+            let [r, g, b] = c.to_f32();
+            Rgb::from_f32(r * s, g * s, b * s)
+        };
+
+        match vis.lic.coloring {
+            LicColoring::Plain => {
+                image.resize(self.width, self.height);
+                for (pixel, &s) in image.pixels.iter_mut().zip(streaks.as_slice()) {
+                    *pixel = vis.color(s);
+                }
+                None
+            }
+            LicColoring::Dye => {
+                self.render_dye(&vis.tone, image);
+                for (pixel, &s) in image.pixels.iter_mut().zip(streaks.as_slice()) {
+                    *pixel = shade(*pixel, s);
+                }
+                None
+            }
+            LicColoring::Speed => {
+                let speed = self.values(&DisplayField::Speed, vis.lic.length)?;
+                let (min, max) = value_range(&speed, vis.range);
+                let span = if max > min { max - min } else { T::ONE };
+
+                image.resize(self.width, self.height);
+                for ((pixel, &v), &s) in image.pixels.iter_mut().zip(speed.as_slice()).zip(streaks.as_slice()) {
+                    *pixel = shade(vis.color(((v - min) / span).clamp(T::ZERO, T::ONE)), s);
+                }
+                Some((min, max))
+            }
+        }
+    }
+
+    /// Draws every tinted scalar field, summed per channel and brought into range by
+    /// `tone`, with obstacles in flat gray.
+    pub fn render_dye(&self, tone: &ToneMapping<T>, image: &mut Image) {
+        image.resize(self.width, self.height);
+
+        let tinted: Vec<_> = self.scalars.iter()
+            .filter_map(|s| s.tint.map(|tint| (s.field.as_slice(), tint.to_f32())))
+            .collect();
+
+        let Scratch { hdr, glow, .. } = &mut image.scratch;
+        hdr.clear();
+        hdr.resize(self.width * self.height, [0.0; 3]);
+        for (i, rgb) in hdr.iter_mut().enumerate() {
+            for (values, tint) in &tinted {
+                let v = values[i].to_f32();
+                for (c, t) in rgb.iter_mut().zip(tint) {
+                    *c += v * t;
+                }
+            }
+        }
+        tone.apply(self.width, self.height, hdr, glow);
+
+        for ((pixel, rgb), &solid) in image.pixels.iter_mut().zip(hdr.iter()).zip(self.obstacles.as_slice()) {
+            *pixel = if solid > T::HALF { OBSTACLE_GRAY } else { Rgb::from_f32(rgb[0], rgb[1], rgb[2]) };
+        }
+    }
+}
+
+fn value_range<T: Real>(values: &Field2D<T>, range: ValueRange<T>) -> (T, T) {
+    let extent = || values.as_slice().iter().fold((T::ZERO, T::ZERO), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    match range {
+        ValueRange::Fixed { min, max } => (min, max),
+        ValueRange::Auto => extent(),
+        ValueRange::Symmetric => {
+            let (lo, hi) = extent();
+            let m = lo.abs().max(hi);
+            (-m, m)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tonemap::ToneOperator;
+
+    #[test]
+    fn switching_fields_keeps_user_settings() {
+        let mut vis = Visualization::<f32>::default();
+        vis.tone.operator = ToneOperator::Reinhard;
+        vis.lic.length = 25.0;
+        vis.reversed = true;
+
+        vis.set_field(DisplayField::VelocityX);
+        assert_eq!((vis.range, vis.colormap.clone()), (ValueRange::Symmetric, Colormap::Coolwarm));
+        vis.set_field(DisplayField::Dye);
+        assert_eq!(vis.tone.operator, ToneOperator::Reinhard);
+        assert_eq!(vis.lic.length, 25.0);
+        assert!(vis.reversed);
+
+        // a colormap picked by hand survives the switch
+        vis.colormap = Colormap::Magma;
+        vis.set_field(DisplayField::Vorticity);
+        assert_eq!(vis.colormap, Colormap::Magma);
+        assert_eq!(vis.range, ValueRange::Symmetric);
+    }
+
+    #[test]
+    fn ppm_has_header_and_pixels() {
+        let mut image = Image::new(2, 1);
+        image.pixels_mut()[1] = Rgb::new(1, 2, 3);
+        let mut out = Vec::new();
+        image.write_ppm(&mut out).unwrap();
+        assert_eq!(out, b"P6\n2 1\n255\n\0\0\0\x01\x02\x03");
+    }
+
+    #[test]
+    fn default_dye_rendering_clamps_summed_tints() {
+        let mut fluid = Fluid::<f32>::with_size(6, 4, 0.5, 0.0, 0.0);
+        fluid.scalars.get_mut(crate::scalar::DYE_R).unwrap().field[(2, 1)] = 0.5;
+        fluid.scalars.get_mut(crate::scalar::DYE_G).unwrap().field[(2, 1)] = 1.5;
+        fluid.obstacles[(0, 0)] = 1.0;
+
+        let mut image = Image::default();
+        for _ in 0..2 {
+            fluid.render_dye(&ToneMapping::default(), &mut image);
+        }
+        assert_eq!(image.pixels()[2 + 6], Rgb::from_f32(0.5, 1.0, 0.0));
+        assert_eq!(image.pixels()[1], Rgb::BLACK);
+        assert_eq!(image.pixels()[0], OBSTACLE_GRAY);
+    }
+
+    fn scalar_row(values: &[f32]) -> Fluid<f32> {
+        let mut fluid = Fluid::with_size(values.len(), 1, 0.5, 0.0, 0.0);
+        let field = fluid.add_scalar("s").field_mut();
+        for (i, &v) in values.iter().enumerate() {
+            field[(i, 0)] = v;
+        }
+        fluid
+    }
+
+    #[test]
+    fn reversed_samples_the_other_end() {
+        let mut vis = Visualization::<f32>::new(DisplayField::Scalar("s".into()));
+        vis.colormap = Colormap::Turbo;
+        vis.reversed = true;
+        for t in [0.0, 0.2, 0.5, 0.9, 1.0] {
+            assert_eq!(vis.color(t), Colormap::Turbo.sample(1.0 - t));
+        }
+
+        let (fluid, mut image) = (scalar_row(&[0.0, 1.0]), Image::default());
+        vis.range = ValueRange::Fixed { min: 0.0, max: 1.0 };
+        fluid.render(&vis, &mut image);
+        assert_eq!(image.pixels(), [Colormap::Turbo.sample(1.0), Colormap::Turbo.sample(0.0)]);
+    }
+
+    #[test]
+    fn symmetric_range_centers_zero_on_the_larger_magnitude() {
+        let mut vis = Visualization::<f32>::new(DisplayField::Scalar("s".into()));
+        vis.range = ValueRange::Symmetric;
+        vis.colormap = Colormap::Coolwarm;
+        let mut image = Image::default();
+
+        assert_eq!(scalar_row(&[-1.0, 0.0, 3.0]).render(&vis, &mut image), Some((-3.0, 3.0)));
+        assert_eq!(image.pixels()[1], Colormap::Coolwarm.sample(0.5));
+        assert_eq!(image.pixels()[2], Colormap::Coolwarm.sample(1.0));
+
+        assert_eq!(scalar_row(&[-4.0, 0.0, 2.0]).render(&vis, &mut image), Some((-4.0, 4.0)));
+        assert_eq!(image.pixels()[0], Colormap::Coolwarm.sample(0.0));
+        assert_eq!(image.pixels()[1], Colormap::Coolwarm.sample(0.5));
+    }
+
+    #[test]
+    fn values_outside_a_fixed_range_clamp() {
+        let mut vis = Visualization::<f32>::new(DisplayField::Scalar("s".into()));
+        vis.range = ValueRange::Fixed { min: 0.0, max: 2.0 };
+        let mut image = Image::default();
+
+        scalar_row(&[-5.0, 0.0, 1.0, 2.0, 9.0]).render(&vis, &mut image);
+        let viridis = Colormap::Viridis;
+        assert_eq!(image.pixels(), [viridis.sample(0.0), viridis.sample(0.0), viridis.sample(0.5), viridis.sample(1.0), viridis.sample(1.0)]);
+    }
+}
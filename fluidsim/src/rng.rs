@@ -0,0 +1,12 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+/// Random number generator used by the simulation; fast, portable and reproducible.
+pub(crate) type SimRng = ChaCha8Rng;
+
+/// Independent stream `stream` of the generator seeded with `seed`.
+pub(crate) fn stream_rng(seed: u64, stream: u64) -> SimRng {
+    let mut rng = SimRng::seed_from_u64(seed);
+    rng.set_stream(stream);
+    rng
+}
@@ -0,0 +1,190 @@
+use crate::color::Rgb;
+use crate::field::Field2D;
+use crate::real::Real;
+
+/// Name of the red dye channel; every [`crate::Fluid`] starts with all three.
+pub const DYE_R: &str = "dye_r";
+/// Name of the green dye channel.
+pub const DYE_G: &str = "dye_g";
+/// Name of the blue dye channel.
+pub const DYE_B: &str = "dye_b";
+
+/// A passive scalar carried by the flow: a dye channel, tracer concentration,
+/// temperature, fluid age and so on.
+#[derive(Clone)]
+pub struct ScalarField<T: Real = f32> {
+    /// Name it is looked up by.
+    pub name: String,
+    //sized to the owning fluid's grid; only the values are handed out mutably
+    pub(crate) field: Field2D<T>,
+    /// Diffusion rate.
+    pub diffusion: T,
+    /// Fraction of the field kept per second of simulated time; 1 never fades. Applied
+    /// as `dissipation^dt` every substep, so the decay does not depend on the timestep.
+    pub dissipation: T,
+    /// Added everywhere per unit of simulated time, e.g. `1.0` for a fluid-age field.
+    pub growth: T,
+    /// Color the renderer adds per unit of this field; `None` keeps it out of the dye view.
+    pub tint: Option<Rgb>,
+}
+
+impl<T: Real> ScalarField<T> {
+
+    /// Empty field that neither diffuses nor fades and is not drawn.
+    pub fn new(name: &str, width: usize, height: usize) -> Self {
+        Self {
+            name: name.to_owned(),
+            field: Field2D::new(width, height),
+            diffusion: T::ZERO,
+            dissipation: T::ONE,
+            growth: T::ZERO,
+            tint: None,
+        }
+    }
+
+    /// Values per cell.
+    pub fn field(&self) -> &Field2D<T> {
+        &self.field
+    }
+
+    /// Values per cell, mutably. Keep the size; the solver expects every field to
+    /// match the grid of the fluid that owns it.
+    pub fn field_mut(&mut self) -> &mut Field2D<T> {
+        &mut self.field
+    }
+}
+
+/// Named scalar fields advected together by the solver, in insertion order.
+#[derive(Clone, Default)]
+pub struct ScalarRegistry<T: Real = f32> {
+    pub(crate) fields: Vec<ScalarField<T>>,
+}
+
+impl<T: Real> ScalarRegistry<T> {
+
+    /// Empty registry.
+    pub fn new() -> Self {
+        Self { fields: Vec::new() }
+    }
+
+    /// Adds a field, replacing any existing field with the same name. Only the owning
+    /// [`crate::Fluid`] inserts, through [`crate::Fluid::add_scalar`], so every field
+    /// matches the grid.
+    pub(crate) fn insert(&mut self, field: ScalarField<T>) -> &mut ScalarField<T> {
+        let idx = match self.index_of(&field.name) {
+            Some(idx) => {
+                self.fields[idx] = field;
+                idx
+            }
+            None => {
+                self.fields.push(field);
+                self.fields.len() - 1
+            }
+        };
+        &mut self.fields[idx]
+    }
+
+    /// Removes and returns the field called `name`.
+    pub fn remove(&mut self, name: &str) -> Option<ScalarField<T>> {
+        self.index_of(name).map(|idx| self.fields.remove(idx))
+    }
+
+    /// Position of the field called `name`.
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|f| f.name == name)
+    }
+
+    /// Field called `name`.
+    pub fn get(&self, name: &str) -> Option<&ScalarField<T>> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    /// Field called `name`, mutably.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut ScalarField<T>> {
+        self.fields.iter_mut().find(|f| f.name == name)
+    }
+
+    /// Number of fields.
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Whether there are no fields.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Fields in insertion order.
+    pub fn iter(&self) -> std::slice::Iter<'_, ScalarField<T>> {
+        self.fields.iter()
+    }
+
+    /// Fields in insertion order, mutably.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, ScalarField<T>> {
+        self.fields.iter_mut()
+    }
+
+    /// Sum of `value * tint` over all tinted fields at `(x, y)`, as unclamped linear RGB.
+    pub fn tinted_color(&self, x: usize, y: usize) -> [T; 3] {
+        let mut rgb = [T::ZERO; 3];
+        for f in &self.fields {
+            if let Some(tint) = f.tint {
+                let v = f.field[(x, y)];
+                for (c, t) in rgb.iter_mut().zip(tint.to_f32()) {
+                    *c += v * T::from_f32(t);
+                }
+            }
+        }
+        rgb
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fluid::Fluid;
+
+    #[test]
+    fn insert_replaces_by_name_in_place() {
+        let mut reg = ScalarRegistry::<f32>::new();
+        reg.insert(ScalarField::new("a", 2, 2));
+        reg.insert(ScalarField::new("b", 2, 2)).diffusion = 1.0;
+        reg.insert(ScalarField::new("a", 2, 2)).growth = 3.0;
+
+        assert_eq!(reg.len(), 2);
+        assert_eq!(reg.index_of("a"), Some(0));
+        assert_eq!(reg.get("a").unwrap().growth, 3.0);
+        assert_eq!(reg.get("b").unwrap().diffusion, 1.0);
+
+        assert_eq!(reg.remove("a").map(|f| f.name), Some(String::from("a")));
+        assert_eq!(reg.index_of("b"), Some(0));
+        assert!(reg.remove("a").is_none());
+        assert!(reg.get_mut("missing").is_none());
+    }
+
+    #[test]
+    fn tinted_color_sums_visible_fields() {
+        let mut reg = ScalarRegistry::<f32>::new();
+        let red = reg.insert(ScalarField::new("red", 2, 2));
+        red.tint = Some(Rgb::new(255, 0, 0));
+        red.field[(1, 0)] = 0.5;
+        let white = reg.insert(ScalarField::new("white", 2, 2));
+        white.tint = Some(Rgb::WHITE);
+        white.field[(1, 0)] = 0.25;
+        let hidden = reg.insert(ScalarField::new("hidden", 2, 2));
+        hidden.field[(1, 0)] = 9.0;
+
+        assert_eq!(reg.tinted_color(1, 0), [0.75, 0.25, 0.25]);
+        assert_eq!(reg.tinted_color(0, 0), [0.0; 3]);
+    }
+
+    #[test]
+    fn fluid_adds_fields_sized_to_its_grid() {
+        let mut fluid = Fluid::<f32>::with_size(12, 8, 0.5, 0.0, 0.0);
+        fluid.add_scalar("temperature").growth = 1.0;
+        let t = fluid.scalars.get("temperature").unwrap();
+        assert_eq!((t.field().width(), t.field().height()), (12, 8));
+        fluid.step(0.5);
+        assert!(fluid.scalars.get("temperature").unwrap().field()[(5, 4)] > 0.0);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::real::Real;
+
+/// Footprint of an emitter, in cells, measured in the emitter's own frame: `u` along
+/// its heading and `v` to the left of it, so shapes turn with the emitter.
+///
+/// Hard-edged shapes get a one-cell linear falloff so float sizes don't alias on the grid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EmitterShape<T: Real = f32> {
+    /// Gaussian blob, `exp(-(r / radius)²)`.
+    Disk {
+        /// Distance at which the weight falls to `1/e`.
+        radius: T,
+    },
+    /// Annulus of `thickness` centered on `radius`.
+    Ring {
+        /// Distance from the center to the middle of the band.
+        radius: T,
+        /// Width of the band.
+        thickness: T,
+    },
+    /// Capsule of `length` along `angle` (relative to the heading), e.g. a slot jet
+    /// when the angle is 90°.
+    Line {
+        /// End to end, not counting the rounded caps.
+        length: T,
+        /// Width across.
+        thickness: T,
+        /// Relative to the heading, in radians.
+        angle: T,
+    },
+    /// Rectangle of `width` along `angle` (relative to the heading) by `height`.
+    Rect {
+        /// Extent along `angle`.
+        width: T,
+        /// Extent across `angle`.
+        height: T,
+        /// Relative to the heading, in radians.
+        angle: T,
+    },
+}
+
+impl<T: Real> Default for EmitterShape<T> {
+    fn default() -> Self {
+        EmitterShape::Disk { radius: T::from_f32(1.5) }
+    }
+}
+
+impl<T: Real> EmitterShape<T> {
+
+    /// Weight in `0..=1` of the cell at offset `(u, v)` from the emitter center.
+    pub fn weight(&self, u: T, v: T) -> T {
+        match *self {
+            EmitterShape::Disk { radius } => {
+                let radius = radius.max(T::from_f32(0.1));
+                (-(u * u + v * v) / (radius * radius)).exp()
+            }
+            EmitterShape::Ring { radius, thickness } => {
+                let d = ((u * u + v * v).sqrt() - radius).abs();
+                coverage(T::HALF * thickness - d)
+            }
+            EmitterShape::Line { length, thickness, angle } => {
+                let (a, b) = rotate(u, v, angle);
+                let along = (a.abs() - T::HALF * length).max(T::ZERO);
+                coverage(T::HALF * thickness - (along * along + b * b).sqrt())
+            }
+            EmitterShape::Rect { width, height, angle } => {
+                let (a, b) = rotate(u, v, angle);
+                coverage(T::HALF * width - a.abs()) * coverage(T::HALF * height - b.abs())
+            }
+        }
+    }
+
+    /// Radius, in cells, outside of which [`EmitterShape::weight`] is negligible.
+    pub fn extent(&self) -> T {
+        let two = T::from_f32(2.0);
+        match *self {
+            EmitterShape::Disk { radius } => two * radius,
+            EmitterShape::Ring { radius, thickness } => radius + T::HALF * thickness + T::ONE,
+            EmitterShape::Line { length, thickness, .. } => T::HALF * (length + thickness) + T::ONE,
+            EmitterShape::Rect { width, height, .. } => T::HALF * (width * width + height * height).sqrt() + T::ONE,
+        }
+    }
+
+    /// Resizes the shape so its characteristic radius is `radius`: the disk or ring radius,
+    /// half the line length, or half the longer rectangle side (keeping the aspect ratio).
+    pub fn set_radius(&mut self, radius: T) {
+        let radius = radius.max(T::ZERO);
+        match self {
+            EmitterShape::Disk { radius: r } | EmitterShape::Ring { radius: r, .. } => *r = radius,
+            EmitterShape::Line { length, .. } => *length = radius + radius,
+            EmitterShape::Rect { width, height, .. } => {
+                let longest = width.max(*height);
+                if longest > T::ZERO {
+                    let scale = (radius + radius) / longest;
+                    *width *= scale;
+                    *height *= scale;
+                }
+            }
+        }
+    }
+
+    /// Inverse of [`EmitterShape::set_radius`].
+    pub fn radius(&self) -> T {
+        match *self {
+            EmitterShape::Disk { radius } | EmitterShape::Ring { radius, .. } => radius,
+            EmitterShape::Line { length, .. } => T::HALF * length,
+            EmitterShape::Rect { width, height, .. } => T::HALF * width.max(height),
+        }
+    }
+}
+
+/// Rotates `(u, v)` by `-angle`, i.e. into a frame turned by `angle`.
+pub(crate) fn rotate<T: Real>(u: T, v: T, angle: T) -> (T, T) {
+    let (s, c) = (angle.sin(), angle.cos());
+    (u * c + v * s, v * c - u * s)
+}
+
+//fraction of a cell inside an edge, given the signed distance to it (positive inside)
+
+fn coverage<T: Real>(inside: T) -> T {
+    (T::HALF + inside).clamp(T::ZERO, T::ONE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disk_is_gaussian() {
+        let disk = EmitterShape::Disk { radius: 2.0f32 };
+        assert_eq!(disk.weight(0.0, 0.0), 1.0);
+        assert!((disk.weight(2.0, 0.0) - (-1.0f32).exp()).abs() < 1e-6);
+        assert_eq!(disk.weight(0.0, 1.5), disk.weight(1.5, 0.0));
+    }
+
+    #[test]
+    fn ring_is_hollow() {
+        let ring = EmitterShape::Ring { radius: 4.0f32, thickness: 1.0 };
+        assert_eq!(ring.weight(0.0, 0.0), 0.0);
+        assert_eq!(ring.weight(4.0, 0.0), 1.0);
+        assert_eq!(ring.weight(0.0, -4.0), 1.0);
+        // edge of the band is half covered
+        assert!((ring.weight(4.5, 0.0) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn line_and_rect_turn_with_their_angle() {
+        let along = EmitterShape::Line { length: 6.0f32, thickness: 1.0, angle: 0.0 };
+        assert_eq!(along.weight(2.5, 0.0), 1.0);
+        assert_eq!(along.weight(0.0, 2.5), 0.0);
+
+        let across = EmitterShape::Line { length: 6.0f32, thickness: 1.0, angle: std::f32::consts::FRAC_PI_2 };
+        assert!(across.weight(2.5, 0.0) < 1e-6);
+        assert!((across.weight(0.0, 2.5) - 1.0).abs() < 1e-6);
+
+        let rect = EmitterShape::Rect { width: 4.0f32, height: 2.0, angle: 0.0 };
+        assert_eq!(rect.weight(1.0, 0.5), 1.0);
+        assert_eq!(rect.weight(0.0, 2.0), 0.0);
+    }
+
+    #[test]
+    fn weight_is_negligible_beyond_extent() {
+        let shapes = [
+            EmitterShape::Disk { radius: 1.5f32 },
+            EmitterShape::Ring { radius: 3.0, thickness: 2.0 },
+            EmitterShape::Line { length: 5.0, thickness: 1.0, angle: 0.3 },
+            EmitterShape::Rect { width: 4.0, height: 3.0, angle: 1.0 },
+        ];
+        for shape in shapes {
+            let r = shape.extent() + 0.01;
+            for k in 0..16 {
+                let a = k as f32 * std::f32::consts::TAU / 16.0;
+                assert!(shape.weight(r * a.cos(), r * a.sin()) < 0.02, "{shape:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn set_radius_round_trips() {
+        let mut rect = EmitterShape::Rect { width: 4.0f32, height: 2.0, angle: 0.0 };
+        rect.set_radius(4.0);
+        assert_eq!(rect, EmitterShape::Rect { width: 8.0, height: 4.0, angle: 0.0 });
+        for mut shape in [EmitterShape::Disk { radius: 1.0f32 }, EmitterShape::Ring { radius: 1.0, thickness: 1.0 }, EmitterShape::Line { length: 1.0, thickness: 1.0, angle: 0.0 }] {
+            shape.set_radius(2.5);
+            assert_eq!(shape.radius(), 2.5);
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use crate::real::Real;
+
+/// Curve that brings unbounded dye levels into displayable `0..=1`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ToneOperator {
+    /// Cuts off at 1, so dense dye saturates to flat color.
+    #[default]
+    Clamp,
+    /// `c * (1 + c / white²) / (1 + c)`; reaches 1 at the white point.
+    Reinhard,
+    /// Filmic curve fitted to the ACES reference transform.
+    Aces,
+    /// `ln(1 + c) / ln(1 + white)`; keeps detail over a very wide range.
+    Log,
+}
+
+impl ToneOperator {
+
+    /// Every operator, for pickers.
+    pub const ALL: [ToneOperator; 4] = [ToneOperator::Clamp, ToneOperator::Reinhard, ToneOperator::Aces, ToneOperator::Log];
+
+    /// Label shown in pickers.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ToneOperator::Clamp => "Clamp",
+            ToneOperator::Reinhard => "Reinhard",
+            ToneOperator::Aces => "ACES",
+            ToneOperator::Log => "Log",
+        }
+    }
+
+    /// Maps one non-negative channel value.
+    pub fn apply(&self, c: f32, white: f32) -> f32 {
+        let c = c.max(0.0);
+        let mapped = match self {
+            ToneOperator::Clamp => c,
+            ToneOperator::Reinhard => c * (1.0 + c / (white * white)) / (1.0 + c),
+            ToneOperator::Aces => (c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14),
+            ToneOperator::Log => (1.0 + c).ln() / (1.0 + white).ln(),
+        };
+        mapped.clamp(0.0, 1.0)
+    }
+}
+
+/// Glow added around dye brighter than `threshold`, before tone mapping.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bloom<T: Real = f32> {
+    /// Channel level above which dye glows.
+    pub threshold: T,
+    /// Amount of the blurred glow added back.
+    pub intensity: T,
+    /// Blur standard deviation, in cells.
+    pub radius: T,
+}
+
+impl<T: Real> Default for Bloom<T> {
+    fn default() -> Self {
+        Self { threshold: T::ONE, intensity: T::HALF, radius: T::from_f32(3.0) }
+    }
+}
+
+/// How summed dye levels become display colors: exposure, optional bloom, a tone
+/// curve, then gamma. The default reproduces plain clamping.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ToneMapping<T: Real = f32> {
+    /// Tone curve.
+    pub operator: ToneOperator,
+    /// In stops; each +1 doubles the dye level before mapping.
+    pub exposure: T,
+    /// Output is raised to `1 / gamma`; 1 leaves it linear.
+    pub gamma: T,
+    /// Level shown as full brightness by [`ToneOperator::Reinhard`] and [`ToneOperator::Log`].
+    pub white: T,
+    /// Glow around bright dye; `None` turns it off.
+    pub bloom: Option<Bloom<T>>,
+}
+
+impl<T: Real> Default for ToneMapping<T> {
+    fn default() -> Self {
+        Self { operator: ToneOperator::Clamp, exposure: T::ZERO, gamma: T::ONE, white: T::from_f32(4.0), bloom: None }
+    }
+}
+
+impl<T: Real> ToneMapping<T> {
+
+    /// Maps a `width * height` buffer of linear colors in place. `glow` holds the bloom
+    /// layer; passing the same one every frame saves reallocating it.
+    pub fn apply(&self, width: usize, height: usize, rgb: &mut [[f32; 3]], glow: &mut Vec<[f32; 3]>) {
+        let scale = self.exposure.to_f32().exp2();
+        for c in rgb.iter_mut() {
+            for v in c.iter_mut() {
+                *v *= scale;
+            }
+        }
+
+        if let Some(bloom) = self.bloom.filter(|b| b.intensity > T::ZERO) {
+            let threshold = bloom.threshold.to_f32();
+            glow.clear();
+            glow.extend(rgb.iter().map(|c| c.map(|v| (v - threshold).max(0.0))));
+
+            //nothing above the threshold, nothing to spread
+            if glow.iter().any(|g| g.iter().any(|&v| v > 0.0)) {
+                blur(width, height, glow, bloom.radius.to_f32());
+                let intensity = bloom.intensity.to_f32();
+                for (c, g) in rgb.iter_mut().zip(glow.iter()) {
+                    for (v, g) in c.iter_mut().zip(g) {
+                        *v += g * intensity;
+                    }
+                }
+            }
+        }
+
+        let (white, inv_gamma) = (self.white.to_f32().max(1e-3), 1.0 / self.gamma.to_f32().max(1e-3));
+        for c in rgb.iter_mut() {
+            for v in c.iter_mut() {
+                *v = self.operator.apply(*v, white).powf(inv_gamma);
+            }
+        }
+    }
+}
+
+//separable gaussian, edges clamped
+
+fn blur(width: usize, height: usize, data: &mut [[f32; 3]], sigma: f32) {
+    let sigma = sigma.max(0.1);
+    let reach = (sigma * 3.0).ceil() as isize;
+    let kernel: Vec<f32> = (-reach..=reach).map(|k| (-(k * k) as f32 / (2.0 * sigma * sigma)).exp()).collect();
+    let total: f32 = kernel.iter().sum();
+
+    let pass = |data: &mut [[f32; 3]], len: usize, lines: usize, at: &dyn Fn(usize, usize) -> usize| {
+        let mut line = vec![[0.0f32; 3]; len];
+        for l in 0..lines {
+            for (i, out) in line.iter_mut().enumerate() {
+                let mut sum = [0.0f32; 3];
+                for (k, w) in kernel.iter().enumerate() {
+                    let src = (i as isize + k as isize - reach).clamp(0, len as isize - 1) as usize;
+                    let c = data[at(l, src)];
+                    for ch in 0..3 {
+                        sum[ch] += c[ch] * w;
+                    }
+                }
+                *out = sum.map(|s| s / total);
+            }
+            for (i, c) in line.iter().enumerate() {
+                data[at(l, i)] = *c;
+            }
+        }
+    };
+
+    pass(data, width, height, &|row, x| row * width + x);
+    pass(data, height, width, &|col, y| y * width + col);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(tone: &ToneMapping<f32>, values: &[f32]) -> Vec<f32> {
+        let mut rgb: Vec<[f32; 3]> = values.iter().map(|&v| [v; 3]).collect();
+        tone.apply(values.len(), 1, &mut rgb, &mut Vec::new());
+        rgb.iter().map(|c| c[0]).collect()
+    }
+
+    #[test]
+    fn default_is_plain_clamping() {
+        let values = [0.0, 0.2, 0.5, 1.0, 1.7, -0.3];
+        assert_eq!(map(&ToneMapping::default(), &values), [0.0, 0.2, 0.5, 1.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn reinhard_and_log_reach_one_at_the_white_point() {
+        for op in [ToneOperator::Reinhard, ToneOperator::Log] {
+            assert!((op.apply(4.0, 4.0) - 1.0).abs() < 1e-6, "{op:?}");
+            assert!(op.apply(3.0, 4.0) < 1.0);
+            assert!(op.apply(1.0, 4.0) < op.apply(2.0, 4.0));
+            assert_eq!(op.apply(0.0, 4.0), 0.0);
+        }
+        assert!((ToneOperator::Aces.apply(100.0, 4.0) - 1.0).abs() < 0.02);
+    }
+
+    #[test]
+    fn exposure_and_gamma() {
+        let tone = ToneMapping { exposure: 1.0, ..ToneMapping::default() };
+        assert_eq!(map(&tone, &[0.25]), [0.5]);
+        let tone = ToneMapping { gamma: 2.0, ..ToneMapping::default() };
+        assert_eq!(map(&tone, &[0.25]), [0.5]);
+    }
+
+    #[test]
+    fn bloom_spreads_only_bright_dye() {
+        let mut values = [0.0f32; 15];
+        values[7] = 3.0;
+        let bloom = ToneMapping { operator: ToneOperator::Log, bloom: Some(Bloom::default()), ..ToneMapping::default() };
+        let plain = ToneMapping { bloom: None, ..bloom };
+        let (glowing, flat) = (map(&bloom, &values), map(&plain, &values));
+        assert!(glowing[5] > 0.0 && flat[5] == 0.0);
+        assert!(glowing[7] > flat[7]);
+
+        // below the threshold, or at zero intensity, bloom changes nothing
+        assert_eq!(map(&bloom, &[0.5, 0.9]), map(&plain, &[0.5, 0.9]));
+        let off = ToneMapping { bloom: Some(Bloom { intensity: 0.0, ..Bloom::default() }), ..plain };
+        assert_eq!(map(&off, &values), flat);
+    }
+
+    #[test]
+    fn blur_keeps_the_total() {
+        let mut data = vec![[0.0f32; 3]; 9 * 9];
+        data[4 * 9 + 4] = [1.0, 2.0, 0.0];
+        blur(9, 9, &mut data, 1.0);
+        let total: f32 = data.iter().map(|c| c[1]).sum();
+        assert!((total - 2.0).abs() < 1e-4);
+        assert!(data[4 * 9 + 3][0] > 0.0 && data[3 * 9 + 4][0] == data[4 * 9 + 3][0]);
+    }
+}
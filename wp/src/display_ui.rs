@@ -0,0 +1,125 @@
+use eframe::egui;
+use fluidsim::{Bloom, Colormap, DisplayField, Gradient, LicColoring, ToneMapping, ToneOperator, ValueRange, Visualization};
+
+use crate::emitter_ui::gradient_editor;
+
+//field picker, value range and colormap; `shown` is the range drawn last frame
+
+pub fn visualization_controls(ui: &mut egui::Ui, vis: &mut Visualization, scalars: &[String], shown: Option<(f32, f32)>) {
+
+    let fields = DisplayField::BUILT_IN.into_iter().chain(scalars.iter().cloned().map(DisplayField::Scalar));
+    egui::ComboBox::from_label("Field")
+        .selected_text(vis.field.name().to_string())
+        .show_ui(ui, |ui| {
+            for field in fields {
+                let name = field.name().to_string();
+                if ui.selectable_label(vis.field == field, name).clicked() && vis.field != field {
+                    vis.set_field(field);
+                }
+            }
+        });
+
+    if vis.field == DisplayField::Dye {
+        tone_controls(ui, &mut vis.tone);
+        return;
+    }
+
+    if vis.field == DisplayField::Lic {
+        ui.add(egui::Slider::new(&mut vis.lic.length, 2.0..=40.0).text("Streak Length"));
+        ui.horizontal(|ui| {
+            ui.label("Color By");
+            ui.selectable_value(&mut vis.lic.coloring, LicColoring::Plain, "Streaks");
+            ui.selectable_value(&mut vis.lic.coloring, LicColoring::Speed, "Speed");
+            ui.selectable_value(&mut vis.lic.coloring, LicColoring::Dye, "Dye");
+        });
+
+        //dye colors ignore the range and colormap
+
+        if vis.lic.coloring == LicColoring::Dye {
+            tone_controls(ui, &mut vis.tone);
+            return;
+        }
+    }
+
+    let names = ["Auto", "Symmetric", "Fixed"];
+    let current = match vis.range {
+        ValueRange::Auto => 0,
+        ValueRange::Symmetric => 1,
+        ValueRange::Fixed { .. } => 2,
+    };
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_source("range")
+            .selected_text(names[current])
+            .show_ui(ui, |ui| {
+                for (i, name) in names.iter().enumerate() {
+                    if ui.selectable_label(i == current, *name).clicked() && i != current {
+
+                        //a fixed range starts from what is on screen now
+
+                        let (min, max) = shown.unwrap_or((0.0, 1.0));
+                        vis.range = match i {
+                            0 => ValueRange::Auto,
+                            1 => ValueRange::Symmetric,
+                            _ => ValueRange::Fixed { min, max },
+                        };
+                    }
+                }
+            });
+        ui.label("Range");
+        if let ValueRange::Fixed { min, max } = &mut vis.range {
+            ui.add(egui::DragValue::new(min).speed(0.01).prefix("min "));
+            ui.add(egui::DragValue::new(max).speed(0.01).prefix("max "));
+        }
+    });
+
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_source("colormap")
+            .selected_text(vis.colormap.name())
+            .show_ui(ui, |ui| {
+                for map in Colormap::BUILT_IN {
+                    let name = map.name();
+                    ui.selectable_value(&mut vis.colormap, map, name);
+                }
+                let custom = matches!(vis.colormap, Colormap::Custom(_));
+                if ui.selectable_label(custom, "Custom").clicked() && !custom {
+                    vis.colormap = Colormap::Custom(Gradient::default());
+                }
+            });
+        ui.label("Colormap");
+        ui.checkbox(&mut vis.reversed, "Reversed");
+    });
+
+    if let Colormap::Custom(gradient) = &mut vis.colormap {
+        gradient_editor(ui, usize::MAX, gradient);
+    }
+}
+
+//exposure, tone curve, gamma and glow for dye
+
+fn tone_controls(ui: &mut egui::Ui, tone: &mut ToneMapping) {
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_source("tone_operator")
+            .selected_text(tone.operator.name())
+            .show_ui(ui, |ui| {
+                for op in ToneOperator::ALL {
+                    ui.selectable_value(&mut tone.operator, op, op.name());
+                }
+            });
+        ui.label("Tone Mapping");
+    });
+    ui.add(egui::Slider::new(&mut tone.exposure, -5.0..=5.0).text("Exposure (EV)"));
+    ui.add(egui::Slider::new(&mut tone.gamma, 0.5..=3.0).text("Gamma"));
+    if matches!(tone.operator, ToneOperator::Reinhard | ToneOperator::Log) {
+        ui.add(egui::Slider::new(&mut tone.white, 1.0..=64.0).logarithmic(true).text("White Point"));
+    }
+
+    let mut bloom = tone.bloom.is_some();
+    if ui.checkbox(&mut bloom, "Bloom").changed() {
+        tone.bloom = bloom.then(Bloom::default);
+    }
+    if let Some(bloom) = &mut tone.bloom {
+        ui.add(egui::Slider::new(&mut bloom.threshold, 0.0..=4.0).text("Threshold"));
+        ui.add(egui::Slider::new(&mut bloom.intensity, 0.0..=2.0).text("Intensity"));
+        ui.add(egui::Slider::new(&mut bloom.radius, 0.5..=10.0).text("Radius"));
+    }
+}
@@ -0,0 +1,432 @@
+use eframe::egui;
+use fluidsim::{ColorMode, Emitter, EmitterKind, EmitterShape, Gradient, GradientStop, Interpolation, Motion, MotionPath, Pulse, RandomWalk, Rgb};
+
+//combo box to pick the shape kind, then sliders for its dimensions
+
+pub fn shape_controls(ui: &mut egui::Ui, id: usize, shape: &mut EmitterShape) {
+
+    let kinds = [
+        ("Disk", EmitterShape::Disk { radius: 1.5 }),
+        ("Ring", EmitterShape::Ring { radius: 4.0, thickness: 1.0 }),
+        ("Line", EmitterShape::Line { length: 8.0, thickness: 1.0, angle: 90f32.to_radians() }),
+        ("Rectangle", EmitterShape::Rect { width: 3.0, height: 6.0, angle: 0.0 }),
+    ];
+
+    let current = kinds.iter().position(|(_, k)| std::mem::discriminant(k) == std::mem::discriminant(shape)).unwrap_or(0);
+
+    egui::ComboBox::from_id_source(("shape", id))
+        .selected_text(kinds[current].0)
+        .show_ui(ui, |ui| {
+            for (i, (name, default)) in kinds.iter().enumerate() {
+                if ui.selectable_label(i == current, *name).clicked() && i != current {
+                    *shape = *default;
+                }
+            }
+        });
+
+    match shape {
+        EmitterShape::Disk { radius } => {
+            ui.add(egui::Slider::new(radius, 0.5..=10.0).text("Radius"));
+        }
+        EmitterShape::Ring { radius, thickness } => {
+            ui.add(egui::Slider::new(radius, 1.0..=20.0).text("Radius"));
+            ui.add(egui::Slider::new(thickness, 0.5..=5.0).text("Thickness"));
+        }
+        EmitterShape::Line { length, thickness, angle } => {
+            ui.add(egui::Slider::new(length, 1.0..=40.0).text("Length"));
+            ui.add(egui::Slider::new(thickness, 0.5..=5.0).text("Thickness"));
+            ui.horizontal(|ui| {
+                ui.drag_angle(angle);
+                ui.label("Shape Angle");
+            });
+        }
+        EmitterShape::Rect { width, height, angle } => {
+            ui.add(egui::Slider::new(width, 1.0..=40.0).text("Width"));
+            ui.add(egui::Slider::new(height, 1.0..=40.0).text("Height"));
+            ui.horizontal(|ui| {
+                ui.drag_angle(angle);
+                ui.label("Shape Angle");
+            });
+        }
+    }
+}
+
+//outline of the shape in grid cells, relative to the emitter center and already turned by its heading
+
+pub fn shape_outline(shape: &EmitterShape, heading: f32) -> Vec<egui::Vec2> {
+
+    let turn = |u: f32, v: f32, a: f32| egui::vec2(u * a.cos() - v * a.sin(), u * a.sin() + v * a.cos());
+
+    let circle = |r: f32| (0..=32).map(|i| {
+        let t = i as f32 / 32.0 * std::f32::consts::TAU;
+        egui::vec2(r * t.cos(), r * t.sin())
+    }).collect();
+
+    match *shape {
+        EmitterShape::Disk { radius } => circle(radius),
+        EmitterShape::Ring { radius, .. } => circle(radius),
+        EmitterShape::Line { length, angle, .. } => {
+            let a = heading + angle;
+            vec![turn(-length * 0.5, 0.0, a), turn(length * 0.5, 0.0, a)]
+        }
+        EmitterShape::Rect { width, height, angle } => {
+            let a = heading + angle;
+            let (w, h) = (width * 0.5, height * 0.5);
+            vec![turn(-w, -h, a), turn(w, -h, a), turn(w, h, a), turn(-w, h, a), turn(-w, -h, a)]
+        }
+    }
+}
+
+//kind picker and the rates that kind uses, all per second of simulated time
+
+pub fn kind_controls(ui: &mut egui::Ui, id: usize, emitter: &mut Emitter) {
+
+    egui::ComboBox::from_id_source(("kind", id))
+        .selected_text(emitter.kind.name())
+        .show_ui(ui, |ui| {
+            for kind in EmitterKind::ALL {
+                ui.selectable_value(&mut emitter.kind, kind, kind.name());
+            }
+        });
+
+    if emitter.kind.emits_dye() {
+        ui.add(egui::Slider::new(&mut emitter.dye_rate, 0.0..=10.0).text("Dye / sec"));
+    }
+
+    match emitter.kind {
+        EmitterKind::Fan => {
+            ui.add(egui::Slider::new(&mut emitter.force, 0.0..=10.0).text("Momentum / sec"));
+        }
+        EmitterKind::Jet => {
+            ui.add(egui::Slider::new(&mut emitter.jet_speed, 0.0..=20.0).text("Jet Speed"));
+            ui.add(egui::Slider::new(&mut emitter.jet_response, 0.1..=20.0).text("Jet Response"));
+        }
+        EmitterKind::Vortex => {
+            ui.add(egui::Slider::new(&mut emitter.force, -10.0..=10.0).text("Swirl / sec"));
+        }
+        EmitterKind::Sink => {
+            ui.add(egui::Slider::new(&mut emitter.force, 0.0..=10.0).text("Inflow / sec"));
+            ui.add(egui::Slider::new(&mut emitter.drain_rate, 0.0..=10.0).text("Drain / sec"));
+        }
+        EmitterKind::DyeSource => {}
+    }
+}
+
+//keyframes, looping, pulsing and on/off schedule for one emitter
+
+pub fn animation_controls(ui: &mut egui::Ui, id: usize, emitter: &mut Emitter, interpolation: &mut Interpolation) {
+
+    egui::CollapsingHeader::new("Animation").id_source(("animation", id)).show(ui, |ui| {
+
+        let time = emitter.time();
+        let anim = &mut emitter.animation;
+        let t = anim.track_time(time);
+
+        ui.label(format!("Time: {:.2}s   Keys: {}", time, anim.position.keys().len()));
+
+        let easings = [
+            ("Step", Interpolation::Step),
+            ("Linear", Interpolation::Linear),
+            ("Smoothstep", Interpolation::Smoothstep),
+            ("Ease In-Out", Interpolation::Bezier { x1: 0.42, y1: 0.0, x2: 0.58, y2: 1.0 }),
+        ];
+        let current = easings.iter().find(|(_, e)| e == interpolation).map_or("Custom", |(name, _)| *name);
+
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source(("easing", id))
+                .selected_text(current)
+                .show_ui(ui, |ui| {
+                    for (name, easing) in easings {
+                        ui.selectable_value(interpolation, easing, name);
+                    }
+                });
+
+            //record every animated property at the current time
+
+            if ui.button("Key All").clicked() {
+                anim.position.insert(t, (emitter.x, emitter.y), *interpolation);
+                anim.angle.insert(t, emitter.angle, *interpolation);
+                anim.strength.insert(t, emitter.strength, *interpolation);
+                anim.color.insert(t, emitter.color, *interpolation);
+                anim.radius.insert(t, emitter.shape.radius(), *interpolation);
+            }
+            if ui.button("Clear Keys").clicked() {
+                anim.position.clear();
+                anim.angle.clear();
+                anim.strength.clear();
+                anim.color.clear();
+                anim.radius.clear();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let mut looped = anim.loop_length.is_some();
+            ui.checkbox(&mut looped, "Loop every");
+            let mut length = anim.loop_length.unwrap_or(10.0);
+            ui.add_enabled(looped, egui::DragValue::new(&mut length).clamp_range(0.1..=600.0).suffix(" s"));
+            anim.loop_length = looped.then_some(length);
+        });
+
+        //pulsing
+
+        let pulses = [
+            ("Constant", Pulse::Constant),
+            ("Square", Pulse::Square { period: 2.0, duty: 0.5 }),
+            ("Sine", Pulse::Sine { period: 2.0 }),
+        ];
+        let current = pulses.iter().position(|(_, p)| std::mem::discriminant(p) == std::mem::discriminant(&anim.pulse)).unwrap_or(0);
+
+        egui::ComboBox::from_id_source(("pulse", id))
+            .selected_text(pulses[current].0)
+            .show_ui(ui, |ui| {
+                for (i, (name, pulse)) in pulses.iter().enumerate() {
+                    if ui.selectable_label(i == current, *name).clicked() && i != current {
+                        anim.pulse = *pulse;
+                    }
+                }
+            });
+
+        match &mut anim.pulse {
+            Pulse::Constant => {}
+            Pulse::Square { period, duty } => {
+                ui.add(egui::Slider::new(period, 0.1..=20.0).text("Period"));
+                ui.add(egui::Slider::new(duty, 0.0..=1.0).text("Duty"));
+            }
+            Pulse::Sine { period } => {
+                ui.add(egui::Slider::new(period, 0.1..=20.0).text("Period"));
+            }
+        }
+
+        //on/off schedule, edits the first window
+
+        let mut scheduled = !anim.schedule.windows.is_empty();
+        ui.checkbox(&mut scheduled, "Schedule");
+        if !scheduled {
+            anim.schedule.windows.clear();
+            return;
+        }
+        if anim.schedule.windows.is_empty() {
+            anim.schedule.windows.push((0.0, 2.0));
+            anim.schedule.period = Some(4.0);
+        }
+
+        let (start, end) = &mut anim.schedule.windows[0];
+        ui.horizontal(|ui| {
+            ui.label("On from");
+            ui.add(egui::DragValue::new(start).clamp_range(0.0..=600.0).speed(0.1));
+            ui.label("to");
+            ui.add(egui::DragValue::new(end).clamp_range(0.0..=600.0).speed(0.1));
+        });
+        *end = end.max(*start);
+
+        ui.horizontal(|ui| {
+            let mut repeat = anim.schedule.period.is_some();
+            ui.checkbox(&mut repeat, "Repeat every");
+            let mut period = anim.schedule.period.unwrap_or(4.0);
+            ui.add_enabled(repeat, egui::DragValue::new(&mut period).clamp_range(0.1..=600.0).suffix(" s"));
+            anim.schedule.period = repeat.then_some(period);
+        });
+    });
+}
+
+//motion mode picker; new modes start around the emitter's current position
+
+pub fn motion_controls(ui: &mut egui::Ui, id: usize, emitter: &mut Emitter) {
+
+    egui::CollapsingHeader::new("Motion").id_source(("motion", id)).show(ui, |ui| {
+
+        let here = (emitter.x, emitter.y);
+        let names = ["Static", "Path", "Orbit", "Lissajous", "Random Walk"];
+        let current = match emitter.motion {
+            Motion::Static => 0,
+            Motion::Path(_) => 1,
+            Motion::Orbit { .. } => 2,
+            Motion::Lissajous { .. } => 3,
+            Motion::RandomWalk(_) => 4,
+        };
+
+        egui::ComboBox::from_id_source(("motion_kind", id))
+            .selected_text(names[current])
+            .show_ui(ui, |ui| {
+                for (i, name) in names.iter().enumerate() {
+                    if ui.selectable_label(i == current, *name).clicked() && i != current {
+                        emitter.motion = match i {
+                            1 => Motion::Path(Box::new(MotionPath::new(Vec::new(), true, 5.0, true))),
+                            2 => Motion::Orbit { center: here, radius: 10.0, angular_speed: 0.5, phase: 0.0 },
+                            3 => Motion::Lissajous { center: here, amplitude: (15.0, 10.0), frequency: (0.1, 0.2), phase: 0.0 },
+                            4 => Motion::RandomWalk(Box::new(RandomWalk::new(here, 10.0, 5.0, 3.0, 0))),
+                            _ => Motion::Static,
+                        };
+                    }
+                }
+            });
+
+        match &mut emitter.motion {
+            Motion::Static => {}
+            Motion::Path(path) => {
+                ui.label(format!("{} points", path.points.len()));
+                ui.horizontal(|ui| {
+                    if ui.button("Add Point Here").on_hover_text("Pause, drag the emitter to each point and add it").clicked() {
+                        path.points.push(here);
+                    }
+                    if ui.button("Clear").clicked() {
+                        path.points.clear();
+                    }
+                });
+                ui.checkbox(&mut path.spline, "Smooth (spline)");
+                ui.checkbox(&mut path.looped, "Loop");
+                ui.add(egui::Slider::new(&mut path.speed, 0.0..=30.0).text("Speed"));
+            }
+            Motion::Orbit { center, radius, angular_speed, .. } => {
+                center_controls(ui, center, here);
+                ui.add(egui::Slider::new(radius, 0.0..=50.0).text("Radius"));
+                ui.add(egui::Slider::new(angular_speed, -3.0..=3.0).text("Angular Speed"));
+            }
+            Motion::Lissajous { center, amplitude, frequency, phase } => {
+                center_controls(ui, center, here);
+                ui.add(egui::Slider::new(&mut amplitude.0, 0.0..=50.0).text("Amplitude X"));
+                ui.add(egui::Slider::new(&mut amplitude.1, 0.0..=50.0).text("Amplitude Y"));
+                ui.add(egui::Slider::new(&mut frequency.0, 0.0..=2.0).text("Frequency X"));
+                ui.add(egui::Slider::new(&mut frequency.1, 0.0..=2.0).text("Frequency Y"));
+                ui.horizontal(|ui| {
+                    ui.drag_angle(phase);
+                    ui.label("Phase");
+                });
+            }
+            Motion::RandomWalk(walk) => {
+                center_controls(ui, &mut walk.center, here);
+                ui.add(egui::Slider::new(&mut walk.range, 1.0..=50.0).text("Range"));
+                ui.add(egui::Slider::new(&mut walk.speed, 0.0..=30.0).text("Speed"));
+                ui.add(egui::Slider::new(&mut walk.turn_rate, 0.0..=10.0).text("Turn Rate"));
+                let mut seed = walk.seed();
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut seed).prefix("Seed: "));
+                    if seed != walk.seed() {
+                        walk.reseed(seed);
+                    }
+                });
+            }
+        }
+    });
+}
+
+fn center_controls(ui: &mut egui::Ui, center: &mut (f32, f32), here: (f32, f32)) {
+    ui.horizontal(|ui| {
+        ui.label(format!("Center: {:.1}, {:.1}", center.0, center.1));
+        if ui.button("Move Here").clicked() {
+            *center = here;
+        }
+    });
+}
+
+//solid color button, or the settings of a cycling palette
+
+pub fn color_controls(ui: &mut egui::Ui, id: usize, emitter: &mut Emitter) {
+
+    let names = ["Solid", "Hue Cycle", "Gradient", "Random per Pulse"];
+    let current = match emitter.color_mode {
+        ColorMode::Solid => 0,
+        ColorMode::HueCycle { .. } => 1,
+        ColorMode::Gradient { .. } => 2,
+        ColorMode::RandomPerPulse { .. } => 3,
+    };
+
+    ui.horizontal(|ui| {
+        ui.label("Color");
+        egui::ComboBox::from_id_source(("color_mode", id))
+            .selected_text(names[current])
+            .show_ui(ui, |ui| {
+                for (i, name) in names.iter().enumerate() {
+                    if ui.selectable_label(i == current, *name).clicked() && i != current {
+                        emitter.color_mode = match i {
+                            1 => ColorMode::HueCycle { speed: 0.1, saturation: 1.0, value: 1.0 },
+                            2 => ColorMode::Gradient { gradient: Gradient::default(), period: 10.0 },
+                            3 => ColorMode::RandomPerPulse { saturation: 0.8, value: 1.0 },
+                            _ => ColorMode::Solid,
+                        };
+                    }
+                }
+            });
+
+        //the live color, editable only when nothing overwrites it
+
+        let mut color = egui::Color32::from(emitter.color);
+        ui.add_enabled_ui(current == 0, |ui| ui.color_edit_button_srgba(&mut color));
+        emitter.color = color.into();
+    });
+
+    match &mut emitter.color_mode {
+        ColorMode::Solid => {}
+        ColorMode::HueCycle { speed, saturation, value } => {
+            ui.add(egui::Slider::new(speed, -1.0..=1.0).text("Hue Speed (turns/s)"));
+            ui.add(egui::Slider::new(saturation, 0.0..=1.0).text("Saturation"));
+            ui.add(egui::Slider::new(value, 0.0..=1.0).text("Value"));
+        }
+        ColorMode::Gradient { gradient, period } => {
+            ui.add(egui::Slider::new(period, 0.5..=60.0).text("Period (s)"));
+            gradient_editor(ui, id, gradient);
+        }
+        ColorMode::RandomPerPulse { saturation, value } => {
+            if matches!(emitter.animation.pulse, Pulse::Constant) {
+                ui.label("Set a pulse under Animation to get a new color each cycle");
+            }
+            ui.add(egui::Slider::new(saturation, 0.0..=1.0).text("Saturation"));
+            ui.add(egui::Slider::new(value, 0.0..=1.0).text("Value"));
+        }
+    }
+}
+
+//preview bar, then one row per stop with its position and color
+
+pub fn gradient_editor(ui: &mut egui::Ui, id: usize, gradient: &mut Gradient) {
+
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width().min(200.0), 16.0), egui::Sense::hover());
+    let painter = ui.painter();
+    let columns = rect.width().max(1.0) as usize;
+    for i in 0..columns {
+        let t = (i as f32 + 0.5) / columns as f32;
+        let x = rect.left() + i as f32;
+        let column = egui::Rect::from_min_max(egui::pos2(x, rect.top()), egui::pos2(x + 1.0, rect.bottom()));
+        painter.rect_filled(column, 0.0, egui::Color32::from(gradient.sample(t)));
+    }
+    for stop in &gradient.stops {
+        let x = rect.left() + stop.position * rect.width();
+        painter.line_segment([egui::pos2(x, rect.bottom() - 4.0), egui::pos2(x, rect.bottom())], egui::Stroke::new(2.0, egui::Color32::WHITE));
+    }
+
+    let mut remove = None;
+    for (i, stop) in gradient.stops.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.push_id(("stop", id, i), |ui| {
+                ui.add(egui::Slider::new(&mut stop.position, 0.0..=1.0));
+                let mut color = egui::Color32::from(stop.color);
+                ui.color_edit_button_srgba(&mut color);
+                stop.color = color.into();
+                if ui.small_button("x").clicked() {
+                    remove = Some(i);
+                }
+            });
+        });
+    }
+    if let Some(i) = remove {
+        gradient.stops.remove(i);
+    }
+
+    //new stops go in the middle of the widest gap, colored to match
+
+    if ui.button("Add Stop").clicked() {
+        let mut edges = vec![0.0];
+        edges.extend(gradient.stops.iter().map(|s| s.position));
+        edges.push(1.0);
+        let (a, b) = edges.windows(2).map(|w| (w[0], w[1])).fold((0.0, 0.0), |best, gap| if gap.1 - gap.0 > best.1 - best.0 { gap } else { best });
+        let position = (a + b) * 0.5;
+        let color = if gradient.stops.is_empty() { Rgb::WHITE } else { gradient.sample(position) };
+        gradient.stops.push(GradientStop { position, color });
+    }
+
+    //resorting mid-drag would swap rows under the pointer
+
+    if !ui.ctx().is_using_pointer() {
+        gradient.sort();
+    }
+}
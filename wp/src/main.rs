@@ -1,92 +1,628 @@
 use eframe::egui;
 use egui::Vec2;
-mod fluid;
-mod emitter;
+mod display_ui;
+mod emitter_ui;
+mod overlay;
 
-use fluid::Fluid;
+use fluidsim::{DYE_B, DYE_G, DYE_R, Emitter, EmitterKind, FlowTraces, Fluid, Image, Interpolation, Rgb, ScalarSource, Simulation, Visualization};
+
+//screen radius of the dot drawn for each emitter
+
+const EMITTER_RADIUS: f32 = 8.0;
+const ARROW_LENGTH: f32 = 20.0;
+//how close a press must land to an arrow tip to grab it
+const ARROW_TIP_RADIUS: f32 = 6.0;
+
+//what a left drag on the canvas does
+
+#[derive(Clone, Copy, PartialEq)]
+enum Tool {
+    Emitters,
+    Stir,
+    Obstacles,
+    Seeds,
+    Particles,
+}
+
+struct StirSettings {
+    //cells
+    radius: f32,
+    //velocity added per cell/second of pointer motion
+    force: f32,
+    dye: bool,
+    color: egui::Color32,
+    dye_amount: f32,
+}
+
+#[derive(Clone, Copy)]
+enum EmitterDrag {
+    //offset from the emitter center to where it was grabbed, so it doesn't jump
+    Move { id: u64, offset: Vec2 },
+    Aim { id: u64 },
+}
 
 struct Sim {
-    fluid: Fluid,
+    sim: Simulation,
+    step_count: usize,
+    new_scalar_name: String,
+    seed: u64,
+    key_interpolation: Interpolation,
+    //id of the emitter being edited, stays valid when others are removed
+    selected: Option<u64>,
+    //grid position the canvas context menu was opened at, and the emitter under it
+    menu_pos: Option<(f32, f32)>,
+    menu_emitter: Option<u64>,
+    //emitter held by the current drag, if the drag started on one
+    drag: Option<EmitterDrag>,
+    tool: Tool,
+    stir: StirSettings,
+    //frame rendered by the library, uploaded into the same texture every frame
+    image: Image,
+    texture: Option<egui::TextureHandle>,
+    filter: egui::TextureFilter,
+    grid_size: [usize; 2],
+    vis: Visualization,
+    //value range the colormap spanned last frame, none when drawing dye
+    legend: Option<(f32, f32)>,
+    obstacle_radius: f32,
+    erase_obstacles: bool,
+    export_status: String,
+    quiver: overlay::QuiverSettings,
+    flow: overlay::FlowSettings,
+    traces: FlowTraces,
+    particles: overlay::ParticleSettings,
 }
 
 impl Default for Sim {
     fn default() -> Self {
         Self {
-            fluid: Fluid::new(0.5, 0.0, 0.0),
+            sim: Simulation::new(Fluid::new(0.5, 0.0, 0.0)),
+            step_count: 10,
+            new_scalar_name: String::new(),
+            seed: 0,
+            key_interpolation: Interpolation::Linear,
+            selected: None,
+            menu_pos: None,
+            menu_emitter: None,
+            drag: None,
+            tool: Tool::Emitters,
+            image: Image::default(),
+            texture: None,
+            filter: egui::TextureFilter::Linear,
+            grid_size: [100, 75],
+            vis: Visualization::default(),
+            legend: None,
+            obstacle_radius: 3.0,
+            erase_obstacles: false,
+            export_status: String::new(),
+            quiver: overlay::QuiverSettings::default(),
+            flow: overlay::FlowSettings::default(),
+            traces: FlowTraces::new(200),
+            particles: overlay::ParticleSettings::default(),
+            stir: StirSettings { radius: 3.0, force: 0.2, dye: true, color: egui::Color32::from_rgb(255, 200, 80), dye_amount: 1.0 },
         }
     }
 }
 
+//screen position of a continuous grid position, cell centers sit on whole numbers
+
+fn grid_to_screen(rect: egui::Rect, cell_w: f32, cell_h: f32, x: f32, y: f32) -> egui::Pos2 {
+    egui::pos2(rect.left() + (x + 0.5) * cell_w, rect.top() + (y + 0.5) * cell_h)
+}
+
 impl Sim {
 
-    //drag emitter to new location
+    fn selected_index(&self) -> Option<usize> {
+        self.selected.and_then(|id| self.sim.fluid.emitter_index(id))
+    }
 
-    fn handle_emitter_drag( &mut self, response: &egui::Response, rect: egui::Rect, cell_w: f32, cell_h: f32, ) {
+    //one solver step at a time, so the traces move through every intermediate velocity field
+
+    fn step(&mut self, n: usize) {
+        for _ in 0..n {
+            self.sim.step_once();
+            let dt = self.sim.fluid.time * self.sim.time_scale;
+            self.traces.advance(&self.sim.fluid, dt);
+        }
+    }
+
+    fn add_emitter_at(&mut self, x: f32, y: f32, kind: EmitterKind) {
+        let mut emitter = Emitter::new(x, y);
+        emitter.kind = kind;
+        emitter.name = format!("{} {}", kind.name(), self.sim.fluid.emitters.len() + 1);
+        let i = self.sim.fluid.add_emitter(emitter);
+        self.selected = Some(self.sim.fluid.emitters[i].id());
+    }
+
+    fn duplicate_emitter(&mut self, i: usize) {
+        let copy = self.sim.fluid.duplicate_emitter(i);
+        let emitter = &mut self.sim.fluid.emitters[copy];
 
-        if let Some(pointer_pos) = response.interact_pointer_pos() {
-            let grid_x = ((pointer_pos.x - rect.left()) / cell_w) as i32;
-            let grid_y = ((pointer_pos.y - rect.top()) / cell_h) as i32;
+        //offset so the copy doesn't hide the original
 
-            if grid_x < 0 || grid_x >= self.fluid.width as i32 || grid_y < 0 || grid_y >= self.fluid.height as i32 {
+        emitter.x = (emitter.x + 3.0).min((self.sim.fluid.width - 1) as f32);
+        emitter.y = (emitter.y + 3.0).min((self.sim.fluid.height - 1) as f32);
+        self.selected = Some(emitter.id());
+    }
+
+    fn delete_emitter(&mut self, i: usize) {
+        let removed = self.sim.fluid.remove_emitter(i);
+        if self.selected == Some(removed.id()) {
+            self.selected = None;
+        }
+    }
+
+    //topmost emitter whose dot contains the screen point
+
+    fn emitter_at(&self, pos: egui::Pos2, rect: egui::Rect, cell_w: f32, cell_h: f32) -> Option<usize> {
+        self.sim.fluid.emitters.iter().rposition(|emitter| {
+            let center = grid_to_screen(rect, cell_w, cell_h, emitter.x, emitter.y);
+            center.distance(pos) <= EMITTER_RADIUS
+        })
+    }
+
+    //click to select, right click for the add/duplicate/delete menu
+
+    fn handle_emitter_selection(&mut self, response: &egui::Response, rect: egui::Rect, cell_w: f32, cell_h: f32) {
+
+        if response.clicked() && let Some(pos) = response.interact_pointer_pos() {
+            self.selected = self.emitter_at(pos, rect, cell_w, cell_h).map(|i| self.sim.fluid.emitters[i].id());
+        }
+
+        if response.secondary_clicked() && let Some(pos) = response.interact_pointer_pos() {
+            self.menu_emitter = self.emitter_at(pos, rect, cell_w, cell_h).map(|i| self.sim.fluid.emitters[i].id());
+            if self.menu_emitter.is_some() {
+                self.selected = self.menu_emitter;
+            }
+            self.menu_pos = Some(((pos.x - rect.left()) / cell_w - 0.5, (pos.y - rect.top()) / cell_h - 0.5));
+        }
+
+        response.context_menu(|ui| {
+            let Some((x, y)) = self.menu_pos else {
+                ui.close_menu();
                 return;
+            };
+
+            //acting on the emitter under the pointer
+
+            if let Some(i) = self.menu_emitter.and_then(|id| self.sim.fluid.emitter_index(id)) {
+                ui.horizontal(|ui| {
+                    ui.label("Name");
+                    ui.text_edit_singleline(&mut self.sim.fluid.emitters[i].name);
+                });
+                if ui.button("Duplicate").clicked() {
+                    self.duplicate_emitter(i);
+                    ui.close_menu();
+                }
+                if ui.button("Delete").clicked() {
+                    self.delete_emitter(i);
+                    ui.close_menu();
+                }
+                return;
+            }
+
+            ui.menu_button("Add Emitter Here", |ui| {
+                for kind in EmitterKind::ALL {
+                    if ui.button(kind.name()).clicked() {
+                        let x = x.clamp(0.0, (self.sim.fluid.width - 1) as f32);
+                        let y = y.clamp(0.0, (self.sim.fluid.height - 1) as f32);
+                        self.add_emitter_at(x, y, kind);
+                        ui.close_menu();
+                    }
+                }
+            });
+        });
+    }
+
+    //drag starts only on an emitter's dot or arrow tip and sticks to that emitter until release
+
+    fn handle_emitter_drag( &mut self, response: &egui::Response, rect: egui::Rect, cell_w: f32, cell_h: f32, ) {
+
+        if response.drag_started_by(egui::PointerButton::Primary) {
+            let origin = response.ctx.input(|i| i.pointer.press_origin());
+            self.drag = origin.and_then(|pos| self.grab_emitter(pos, rect, cell_w, cell_h));
+            if let Some(EmitterDrag::Move { id, .. } | EmitterDrag::Aim { id }) = self.drag {
+                self.selected = Some(id);
             }
+        }
+
+        if response.drag_stopped() {
+            self.drag = None;
+        }
+
+        let (Some(drag), Some(pointer_pos)) = (self.drag, response.interact_pointer_pos()) else {
+            return;
+        };
+
+        let max_x = (self.sim.fluid.width - 1) as f32;
+        let max_y = (self.sim.fluid.height - 1) as f32;
 
-            let mut closest = None;
-            let mut closest_dist = f32::MAX;
+        match drag {
+            EmitterDrag::Move { id, offset } => {
+                let Some(i) = self.sim.fluid.emitter_index(id) else {
+                    self.drag = None;
+                    return;
+                };
 
-            for (i, emitter) in self.fluid.emitters.iter().enumerate() {
-                let dx = emitter.x as f32 - grid_x as f32;
-                let dy = emitter.y as f32 - grid_y as f32;
-                let dist = dx * dx + dy * dy;
+                //continuous grid position, cell centers sit on whole numbers
 
-                if dist < closest_dist {
-                    closest_dist = dist;
-                    closest = Some(i);
+                let pos = pointer_pos - offset;
+                let emitter = &mut self.sim.fluid.emitters[i];
+                emitter.x = ((pos.x - rect.left()) / cell_w - 0.5).clamp(0.0, max_x);
+                emitter.y = ((pos.y - rect.top()) / cell_h - 0.5).clamp(0.0, max_y);
+            }
+            EmitterDrag::Aim { id } => {
+                let Some(i) = self.sim.fluid.emitter_index(id) else {
+                    self.drag = None;
+                    return;
+                };
+
+                //the arrow is drawn in screen space, so aim in screen space too
+
+                let emitter = &mut self.sim.fluid.emitters[i];
+                let center = grid_to_screen(rect, cell_w, cell_h, emitter.x, emitter.y);
+                let d = pointer_pos - center;
+                if d.length() > 1.0 {
+                    emitter.angle = d.y.atan2(d.x).rem_euclid(std::f32::consts::TAU);
                 }
             }
+        }
+    }
+
+    //arrow tips win over dots so a jet's own dot doesn't hide the handle
+
+    fn grab_emitter(&self, pos: egui::Pos2, rect: egui::Rect, cell_w: f32, cell_h: f32) -> Option<EmitterDrag> {
+
+        for emitter in self.sim.fluid.emitters.iter().rev() {
+            if !emitter.kind.is_directional() {
+                continue;
+            }
+            let center = grid_to_screen(rect, cell_w, cell_h, emitter.x, emitter.y);
+            let tip = center + ARROW_LENGTH * egui::vec2(emitter.angle.cos(), emitter.angle.sin());
+            if tip.distance(pos) <= ARROW_TIP_RADIUS {
+                return Some(EmitterDrag::Aim { id: emitter.id() });
+            }
+        }
+
+        let i = self.emitter_at(pos, rect, cell_w, cell_h)?;
+        let emitter = &self.sim.fluid.emitters[i];
+        let center = grid_to_screen(rect, cell_w, cell_h, emitter.x, emitter.y);
+        Some(EmitterDrag::Move { id: emitter.id(), offset: pos - center })
+    }
+
+    //push the fluid along the pointer's path this frame
+
+    fn handle_stir(&mut self, response: &egui::Response, rect: egui::Rect, cell_w: f32, cell_h: f32) {
+
+        if !response.dragged_by(egui::PointerButton::Primary) {
+            return;
+        }
+        let Some(pos) = response.interact_pointer_pos() else {
+            return;
+        };
+
+        let delta = response.drag_delta();
+        let dt = response.ctx.input(|i| i.stable_dt).max(1e-3);
+        let to = ((pos.x - rect.left()) / cell_w - 0.5, (pos.y - rect.top()) / cell_h - 0.5);
+        let from = (to.0 - delta.x / cell_w, to.1 - delta.y / cell_h);
+        let velocity = (delta.x / cell_w / dt * self.stir.force, delta.y / cell_h / dt * self.stir.force);
+        let dye = self.stir.dye.then_some((Rgb::from(self.stir.color), self.stir.dye_amount));
+
+        self.sim.fluid.stir(from, to, velocity, self.stir.radius, dye);
+    }
+
+    fn handle_obstacle_brush(&mut self, response: &egui::Response, rect: egui::Rect, cell_w: f32, cell_h: f32) {
+
+        if !response.is_pointer_button_down_on() {
+            return;
+        }
+        if let Some(pos) = response.interact_pointer_pos() {
+            let (x, y) = ((pos.x - rect.left()) / cell_w - 0.5, (pos.y - rect.top()) / cell_h - 0.5);
+            self.sim.fluid.paint_obstacle(x, y, self.obstacle_radius, !self.erase_obstacles);
+        }
+    }
+
+    //draw the dye into the canvas texture, creating it on the first frame
+
+    fn draw_fluid(&mut self, ctx: &egui::Context, painter: &egui::Painter, rect: egui::Rect) {
+
+        self.legend = self.sim.fluid.render(&self.vis, &mut self.image);
+
+        let size = [self.image.width(), self.image.height()];
+        let pixels = self.image.pixels().iter().map(|&c| egui::Color32::from(c)).collect();
+        let frame = egui::ColorImage { size, pixels };
+        let options = egui::TextureOptions { magnification: self.filter, minification: self.filter, ..Default::default() };
+
+        let texture = match &mut self.texture {
+            Some(texture) => {
+                texture.set(frame, options);
+                texture
+            }
+            None => self.texture.insert(ctx.load_texture("fluid", frame, options)),
+        };
+
+        let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+        painter.image(texture.id(), rect, uv, egui::Color32::WHITE);
+    }
 
-            if let Some(i) = closest {
-                self.fluid.emitters[i].x = grid_x as usize;
-                self.fluid.emitters[i].y = grid_y as usize;
+    //renders the current view at grid resolution into the working directory, numbering
+    //the file so exporting the same step twice never overwrites the first one
+
+    fn export_image(&self) -> String {
+        let mut image = Image::default();
+        self.sim.fluid.render(&self.vis, &mut image);
+
+        let mut n = 0;
+        let (file, path) = loop {
+            let path = match n {
+                0 => format!("fluid_{:06}.ppm", self.sim.steps),
+                n => format!("fluid_{:06}_{n}.ppm", self.sim.steps),
+            };
+            match std::fs::File::create_new(&path) {
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => n += 1,
+                file => break (file, path),
             }
+        };
+        match file.and_then(|file| image.write_ppm(std::io::BufWriter::new(file))) {
+            Ok(()) => format!("Saved {path}"),
+            Err(err) => format!("Export failed: {err}"),
+        }
+    }
+
+    //vertical color bar in the bottom right corner, high values on top
+
+    fn draw_legend(&self, painter: &egui::Painter, rect: egui::Rect) {
+
+        let Some((min, max)) = self.legend else {
+            return;
+        };
+
+        let bar = egui::Rect::from_min_size(rect.right_bottom() + egui::vec2(-90.0, -170.0), egui::vec2(16.0, 150.0));
+
+        //backdrop sized to the labels once they're laid out
+
+        let backdrop = painter.add(egui::Shape::Noop);
+        let mut covered = bar;
+
+        let rows = bar.height() as usize;
+        for i in 0..rows {
+            let t = 1.0 - (i as f32 + 0.5) / rows as f32;
+            let y = bar.top() + i as f32;
+            let row = egui::Rect::from_min_max(egui::pos2(bar.left(), y), egui::pos2(bar.right(), y + 1.0));
+            painter.rect_filled(row, 0.0, egui::Color32::from(self.vis.color(t)));
+        }
+
+        let font = egui::FontId::monospace(11.0);
+        for (t, value) in [(0.0, max), (0.5, (min + max) * 0.5), (1.0, min)] {
+            let pos = egui::pos2(bar.right() + 4.0, bar.top() + t * bar.height());
+            covered = covered.union(painter.text(pos, egui::Align2::LEFT_CENTER, format!("{value:.3}"), font.clone(), egui::Color32::WHITE));
         }
+        let title = egui::pos2(covered.right(), bar.top() - 8.0);
+        covered = covered.union(painter.text(title, egui::Align2::RIGHT_BOTTOM, self.vis.field.name(), font, egui::Color32::WHITE));
+
+        painter.set(backdrop, egui::Shape::rect_filled(covered.expand(6.0), 4.0, egui::Color32::from_black_alpha(160)));
     }
 
-    //draw emitter as circle with arrow for direction
+    //draw emitter as circle with arrow for direction (fans and jets) or a ring (vortices and sinks)
 
     fn draw_emitters( &self, painter: &egui::Painter, rect: egui::Rect, cell_w: f32, cell_h: f32, ) {
 
-        for (_i, emitter) in self.fluid.emitters.iter().enumerate() {
-            let emitter_x = rect.left() + emitter.x as f32 * cell_w + cell_w * 0.5;
-            let emitter_y = rect.top() + emitter.y as f32 * cell_h + cell_h * 0.5;
+        for emitter in &self.sim.fluid.emitters {
+
+            let selected = self.selected == Some(emitter.id());
+
+            //where the emitter's motion takes it
+
+            let path: Vec<egui::Pos2> = emitter.motion.overlay().into_iter()
+                .map(|(x, y)| egui::pos2(rect.left() + (x + 0.5) * cell_w, rect.top() + (y + 0.5) * cell_h))
+                .collect();
+            if path.len() > 1 {
+                painter.add(egui::Shape::dashed_line(&path, egui::Stroke::new(1.0, egui::Color32::from_white_alpha(90)), 6.0, 4.0));
+            }
+
+            let emitter_x = rect.left() + emitter.x * cell_w + cell_w * 0.5;
+            let emitter_y = rect.top() + emitter.y * cell_h + cell_h * 0.5;
 
             let center = egui::pos2(emitter_x, emitter_y);
 
-            painter.circle_filled(center, 8.0, emitter.color, );
+            let outline: Vec<egui::Pos2> = emitter_ui::shape_outline(&emitter.shape, emitter.angle).into_iter()
+                .map(|p| center + egui::vec2(p.x * cell_w, p.y * cell_h))
+                .collect();
+            let outline_color = if selected { egui::Color32::YELLOW } else { egui::Color32::from_white_alpha(120) };
+            painter.add(egui::Shape::line(outline, egui::Stroke::new(1.0, outline_color)));
+
+            if selected {
+                painter.circle_stroke(center, EMITTER_RADIUS + 8.0, egui::Stroke::new(2.0, egui::Color32::YELLOW));
+            }
+
+            let fill = if emitter.kind.emits_dye() { egui::Color32::from(emitter.color) } else { egui::Color32::GRAY };
+            painter.circle_filled(center, EMITTER_RADIUS, fill, );
+
+            if !emitter.kind.is_directional() {
+                painter.circle_stroke(center, 12.0, egui::Stroke::new(2.0, egui::Color32::WHITE), );
+                continue;
+            }
 
             let dir_x = emitter.angle.cos();
             let dir_y = emitter.angle.sin();
 
-            let arrow_length = 20.0;
-            let arrow_tip = egui::pos2(emitter_x + dir_x * arrow_length, emitter_y + dir_y * arrow_length, );
+            let arrow_tip = egui::pos2(emitter_x + dir_x * ARROW_LENGTH, emitter_y + dir_y * ARROW_LENGTH, );
 
             painter.line_segment([center, arrow_tip], egui::Stroke::new(2.0, egui::Color32::WHITE), );
+            painter.circle_filled(arrow_tip, 3.0, egui::Color32::WHITE, );
         }
     }
 }
 
 impl eframe::App for Sim {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        
+
+        //
+        // playback controls
+        //
+
+        if !ctx.wants_keyboard_input() && ctx.input(|i| i.key_pressed(egui::Key::Space)) {
+            self.sim.toggle_play();
+        }
+
+        if !ctx.wants_keyboard_input() && ctx.input(|i| i.key_pressed(egui::Key::Delete)) && let Some(i) = self.selected_index() {
+            self.delete_emitter(i);
+        }
+
+        egui::Window::new("Tools").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.tool, Tool::Emitters, "Emitters").on_hover_text("Drag emitters and their arrows");
+                ui.selectable_value(&mut self.tool, Tool::Stir, "Stir").on_hover_text("Drag to push the fluid");
+                ui.selectable_value(&mut self.tool, Tool::Obstacles, "Obstacles").on_hover_text("Drag to paint solid cells");
+                ui.selectable_value(&mut self.tool, Tool::Seeds, "Seeds").on_hover_text("Click to add flow line seeds");
+                ui.selectable_value(&mut self.tool, Tool::Particles, "Particles").on_hover_text("Hold to release tracer particles");
+            });
+            if self.tool == Tool::Stir {
+                ui.add(egui::Slider::new(&mut self.stir.radius, 0.5..=15.0).text("Radius"));
+                ui.add(egui::Slider::new(&mut self.stir.force, 0.0..=1.0).text("Force"));
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.stir.dye, "Dye");
+                    ui.color_edit_button_srgba(&mut self.stir.color);
+                });
+                ui.add_enabled(self.stir.dye, egui::Slider::new(&mut self.stir.dye_amount, 0.0..=5.0).text("Dye Amount"));
+            }
+            if self.tool == Tool::Obstacles {
+                ui.add(egui::Slider::new(&mut self.obstacle_radius, 0.5..=15.0).text("Brush Radius"));
+                ui.checkbox(&mut self.erase_obstacles, "Erase");
+                if ui.button("Clear All").clicked() {
+                    self.sim.fluid.obstacles.fill(0.0);
+                }
+            }
+        });
+
+        egui::Window::new("Display").show(ctx, |ui| {
+            let scalars: Vec<String> = self.sim.fluid.scalars.iter().map(|s| s.name.clone()).collect();
+            display_ui::visualization_controls(ui, &mut self.vis, &scalars, self.legend);
+
+            ui.separator();
+            overlay::quiver_controls(ui, &mut self.quiver);
+            overlay::flow_controls(ui, &mut self.flow, &mut self.traces, &self.sim.fluid);
+            overlay::particle_controls(ui, &mut self.particles, &mut self.sim.fluid.particles);
+            ui.separator();
+
+            //writes what the canvas shows, without the overlays
+
+            ui.horizontal(|ui| {
+                if ui.button("Export Image").clicked() {
+                    self.export_status = self.export_image();
+                }
+                ui.label(&self.export_status);
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Filtering");
+                ui.selectable_value(&mut self.filter, egui::TextureFilter::Nearest, "Nearest");
+                ui.selectable_value(&mut self.filter, egui::TextureFilter::Linear, "Bilinear");
+            });
+
+            //rebuilds the default scene, so anything unsaved is lost
+
+            ui.horizontal(|ui| {
+                ui.label("Grid");
+                ui.add(egui::DragValue::new(&mut self.grid_size[0]).clamp_range(16..=1024));
+                ui.label("x");
+                ui.add(egui::DragValue::new(&mut self.grid_size[1]).clamp_range(16..=1024));
+                if ui.button("Apply").clicked() {
+                    let [width, height] = self.grid_size;
+                    let mut fluid = Fluid::with_size(width, height, 0.5, 0.0, 0.0);
+                    fluid.reseed(self.seed);
+                    self.sim = Simulation::new(fluid);
+                    self.traces.restart();
+                    self.selected = None;
+                    self.drag = None;
+                }
+            });
+        });
+
+        egui::Window::new("Playback").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if self.sim.playing {
+                    if ui.button("Pause").clicked() {
+                        self.sim.pause();
+                    }
+                } else if ui.button("Play").clicked() {
+                    self.sim.play();
+                }
+                if ui.button("Step").clicked() {
+                    self.step(1);
+                }
+                if ui.button("Reset").clicked() {
+                    self.sim.reset();
+                    self.traces.restart();
+                }
+                if ui.button("Save Scene").on_hover_text("Use the current state as the reset point").clicked() {
+                    self.sim.set_initial_scene();
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Step N").clicked() {
+                    self.step(self.step_count);
+                }
+                ui.add(egui::DragValue::new(&mut self.step_count).clamp_range(1..=1000));
+            });
+            ui.add(egui::Slider::new(&mut self.sim.time_scale, 0.1..=4.0).text("Time Scale"));
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut self.seed).prefix("Seed: "));
+                if ui.button("Reseed").on_hover_text("Reset the scene with this seed").clicked() {
+                    self.sim.reseed(self.seed);
+                    self.traces.restart();
+                }
+            });
+            ui.label(format!("Steps: {}   Time: {:.2}", self.sim.steps, self.sim.elapsed));
+
+            ui.collapsing("Diagnostics", |ui| {
+                let d = self.sim.fluid.diagnostics();
+                ui.label(format!("Total dye: {:.2}", d.total_dye));
+                ui.label(format!("Kinetic energy: {:.2}", d.kinetic_energy));
+                ui.label(format!("Max speed: {:.3}", d.max_speed));
+                ui.label(format!("Max divergence: {:.4}", d.max_divergence));
+            });
+        });
+
         //
         // fluid controls
         //
 
         egui::Window::new("Fluid Controls").show(ctx, |ui| {
-            ui.add(egui::Slider::new(&mut self.fluid.time, 0.01..=2.0).text("Timestep"));
-            ui.add(egui::Slider::new(&mut self.fluid.viscosity, 0.0..=0.0001).text("Viscosity"));
-            ui.add(egui::Slider::new(&mut self.fluid.diffusion, 0.0..=0.01).text("Diffusion"));
-            ui.add(egui::Slider::new(&mut self.fluid.dissipation, 0.901..=1.01).text("Dissipation"));
+            ui.add(egui::Slider::new(&mut self.sim.fluid.time, 0.01..=2.0).text("Timestep"));
+            ui.add(egui::Slider::new(&mut self.sim.fluid.viscosity, 0.0..=0.0001).text("Viscosity"));
+            ui.add(egui::Slider::new(&mut self.sim.fluid.substeps, 1..=8).text("Substeps"));
+
+            //per scalar field settings
+
+            ui.separator();
+            ui.label("Scalar Fields");
+            for scalar in self.sim.fluid.scalars.iter_mut() {
+                ui.collapsing(scalar.name.clone(), |ui| {
+                    ui.add(egui::Slider::new(&mut scalar.diffusion, 0.0..=0.01).text("Diffusion"));
+                    ui.add(egui::Slider::new(&mut scalar.dissipation, 0.5..=1.0).text("Kept per Second"));
+                    ui.add(egui::Slider::new(&mut scalar.growth, 0.0..=1.0).text("Growth"));
+                    ui.horizontal(|ui| {
+                        let mut visible = scalar.tint.is_some();
+                        ui.checkbox(&mut visible, "Render");
+                        if visible {
+                            let mut tint = egui::Color32::from(scalar.tint.unwrap_or(Rgb::WHITE));
+                            ui.color_edit_button_srgba(&mut tint);
+                            scalar.tint = Some(tint.into());
+                        } else {
+                            scalar.tint = None;
+                        }
+                    });
+                });
+            }
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.new_scalar_name);
+                let name = self.new_scalar_name.trim();
+                if ui.button("Add Field").clicked() && !name.is_empty() {
+                    self.sim.fluid.add_scalar(name);
+                    self.new_scalar_name.clear();
+                }
+            });
         });
 
         //
@@ -94,30 +630,99 @@ impl eframe::App for Sim {
         //
 
         egui::Window::new("Emitter Controls").show(ctx, |ui| {
-            for (i, emitter) in self.fluid.emitters.iter_mut().enumerate() {
-                let mut angle_deg = emitter.angle.to_degrees();
-                ui.group(|ui| {
-                    ui.label(format!("Emitter {}", i + 1));
-                    ui.add(egui::Slider::new(&mut emitter.strength, 0.0..=10.0).text("Strength"));
-                    ui.add(egui::Slider::new(&mut emitter.rotation_speed, -1.0..=1.0).text("Rotation Speed"));
-                    ui.add(egui::Slider::new(&mut emitter.radius, 1..=3).text("Radius"));
-                    ui.add(egui::Slider::new(&mut angle_deg, 0.0..=360.0).text("Angle (°)"));
-                    ui.color_edit_button_srgba(&mut emitter.color);
+            let extra_scalars: Vec<String> = self.sim.fluid.scalars.iter()
+                .map(|s| s.name.clone())
+                .filter(|name| ![DYE_R, DYE_G, DYE_B].contains(&name.as_str()))
+                .collect();
+
+            if ui.button("Add Emitter").on_hover_text("Or right click the canvas").clicked() {
+                let (x, y) = (self.sim.fluid.width as f32 * 0.5, self.sim.fluid.height as f32 * 0.5);
+                self.add_emitter_at(x, y, EmitterKind::default());
+            }
+
+            //one row per emitter, click the name to select
+
+            let mut duplicate = None;
+            let mut delete = None;
+
+            for (i, emitter) in self.sim.fluid.emitters.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui.selectable_label(self.selected == Some(emitter.id()), &emitter.name).clicked() {
+                        self.selected = Some(emitter.id());
+                    }
+                    if ui.small_button("Duplicate").clicked() {
+                        duplicate = Some(i);
+                    }
+                    if ui.small_button("Delete").clicked() {
+                        delete = Some(i);
+                    }
                 });
+            }
+
+            if let Some(i) = duplicate {
+                self.duplicate_emitter(i);
+            }
+            if let Some(i) = delete {
+                self.delete_emitter(i);
+            }
 
-                //for angle slider
+            ui.separator();
 
-                if angle_deg >= 360.0 {
-                    angle_deg -= 360.0;
-                } else if angle_deg < 0.0 {
-                    angle_deg += 360.0;
+            let Some(i) = self.selected_index() else {
+                ui.label("Select an emitter to edit it");
+                return;
+            };
+
+            let emitter = &mut self.sim.fluid.emitters[i];
+
+            //widget state follows the emitter, not its place in the list
+
+            let id = emitter.id() as usize;
+            let mut angle_deg = emitter.angle.to_degrees();
+            ui.horizontal(|ui| {
+                ui.label("Name");
+                ui.text_edit_singleline(&mut emitter.name);
+            });
+            ui.add(egui::Slider::new(&mut emitter.strength, 0.0..=10.0).text("Strength"));
+            emitter_ui::kind_controls(ui, id, emitter);
+            ui.add(egui::Slider::new(&mut emitter.rotation_speed, -1.0..=1.0).text("Rotation Speed"));
+            ui.add(egui::Slider::new(&mut emitter.particle_rate, 0.0..=200.0).text("Particles / sec"));
+            emitter_ui::shape_controls(ui, id, &mut emitter.shape);
+            ui.add(egui::Slider::new(&mut angle_deg, 0.0..=360.0).text("Angle (°)"));
+            emitter_ui::animation_controls(ui, id, emitter, &mut self.key_interpolation);
+            emitter_ui::motion_controls(ui, id, emitter);
+
+            //for angle slider
+
+            if angle_deg >= 360.0 {
+                angle_deg -= 360.0;
+            } else if angle_deg < 0.0 {
+                angle_deg += 360.0;
+            }
+            emitter.angle = angle_deg.to_radians();
+
+            if !emitter.kind.emits_dye() {
+                return;
+            }
+
+            emitter_ui::color_controls(ui, id, emitter);
+
+            //amount fed into each non-dye scalar field
+
+            for name in &extra_scalars {
+                let mut amount = emitter.sources.iter().find(|s| &s.name == name).map_or(0.0, |s| s.amount);
+                if ui.add(egui::Slider::new(&mut amount, 0.0..=5.0).text(name)).changed() {
+                    emitter.sources.retain(|s| &s.name != name);
+                    if amount > 0.0 {
+                        emitter.sources.push(ScalarSource { name: name.clone(), amount });
+                    }
                 }
-                emitter.angle = angle_deg.to_radians();
             }
         });
 
-
-        self.fluid.step();
+        if self.sim.playing {
+            self.step(1);
+        }
 
         //
         // rendering
@@ -128,28 +733,34 @@ impl eframe::App for Sim {
             let (response, painter) = ui.allocate_painter(ui.available_size(), egui::Sense::click_and_drag());
             let rect = response.rect;
 
-            let cell_w = rect.width() / self.fluid.width as f32;
-            let cell_h = rect.height() / self.fluid.height as f32;
+            let cell_w = rect.width() / self.sim.fluid.width as f32;
+            let cell_h = rect.height() / self.sim.fluid.height as f32;
 
-            for y in 0..self.fluid.height {
-                for x in 0..self.fluid.width {
-                    let idx = self.fluid.index(x, y);
-                
-                    let r = self.fluid.density_r[idx].clamp(0.0, 1.0);
-                    let g = self.fluid.density_g[idx].clamp(0.0, 1.0);
-                    let b = self.fluid.density_b[idx].clamp(0.0, 1.0);
-                
-                    let color = egui::Color32::from_rgb((r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8, );
-                
-                    let px = rect.left() + x as f32 * cell_w;
-                    let py = rect.top() + y as f32 * cell_h;
-                
-                    painter.rect_filled(egui::Rect::from_min_size(egui::pos2(px, py), Vec2::new(cell_w, cell_h)), 0.0,color, );
+            self.draw_fluid(ctx, &painter, rect);
+
+            match self.tool {
+                Tool::Emitters => {
+                    self.handle_emitter_selection(&response, rect, cell_w, cell_h);
+                    self.handle_emitter_drag(&response, rect, cell_w, cell_h);
+                }
+                Tool::Stir => self.handle_stir(&response, rect, cell_w, cell_h),
+                Tool::Obstacles => self.handle_obstacle_brush(&response, rect, cell_w, cell_h),
+                Tool::Particles => {
+                    if response.is_pointer_button_down_on() && let Some(pos) = response.interact_pointer_pos() {
+                        self.sim.fluid.seed_particle((pos.x - rect.left()) / cell_w - 0.5, (pos.y - rect.top()) / cell_h - 0.5);
+                    }
+                }
+                Tool::Seeds => {
+                    if response.clicked() && let Some(pos) = response.interact_pointer_pos() {
+                        self.traces.add_seed(((pos.x - rect.left()) / cell_w - 0.5, (pos.y - rect.top()) / cell_h - 0.5));
+                    }
                 }
             }
-
-            self.handle_emitter_drag(&response, rect, cell_w, cell_h);
+            overlay::draw_quiver(&painter, &self.sim.fluid, &self.quiver, rect, cell_w, cell_h);
+            overlay::draw_flow_lines(&painter, &self.sim.fluid, &self.traces, &self.flow, rect, cell_w, cell_h);
+            overlay::draw_particles(&painter, &self.sim.fluid, &self.particles, rect, cell_w, cell_h);
             self.draw_emitters(&painter, rect, cell_w, cell_h);
+            self.draw_legend(&painter, rect);
 
         });
 
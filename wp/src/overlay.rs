@@ -0,0 +1,235 @@
+use eframe::egui;
+use fluidsim::{FlowTraces, Fluid, Inertia, Integrator, ParticleColor, ParticleSystem};
+
+use crate::grid_to_screen;
+
+pub struct QuiverSettings {
+    pub enabled: bool,
+    //cells between arrows
+    pub spacing: usize,
+    //arrow length in cells per unit of speed
+    pub scale: f32,
+    pub color: egui::Color32,
+}
+
+pub struct FlowSettings {
+    pub streamlines: bool,
+    pub pathlines: bool,
+    pub streaklines: bool,
+    //cells between seeds placed by "Seed Grid"
+    pub grid_spacing: usize,
+    //longest streamline each way from its seed, in cells
+    pub length: f32,
+}
+
+impl Default for FlowSettings {
+    fn default() -> Self {
+        Self { streamlines: true, pathlines: false, streaklines: false, grid_spacing: 10, length: 60.0 }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ParticleStyle {
+    Hidden,
+    Points,
+    Trails,
+}
+
+pub struct ParticleSettings {
+    pub style: ParticleStyle,
+    //screen pixels
+    pub size: f32,
+}
+
+impl Default for ParticleSettings {
+    fn default() -> Self {
+        Self { style: ParticleStyle::Trails, size: 1.5 }
+    }
+}
+
+const STREAMLINE_COLOR: egui::Color32 = egui::Color32::from_rgba_premultiplied(200, 200, 200, 200);
+const PATHLINE_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 210, 60);
+const STREAKLINE_COLOR: egui::Color32 = egui::Color32::from_rgb(80, 220, 255);
+//streamline integration step, in cells
+const STREAMLINE_STEP: f32 = 0.5;
+
+impl Default for QuiverSettings {
+    fn default() -> Self {
+        Self { enabled: false, spacing: 5, scale: 1.5, color: egui::Color32::from_white_alpha(200) }
+    }
+}
+
+pub fn quiver_controls(ui: &mut egui::Ui, quiver: &mut QuiverSettings) {
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut quiver.enabled, "Velocity Arrows");
+        ui.color_edit_button_srgba(&mut quiver.color);
+    });
+    if quiver.enabled {
+        ui.add(egui::Slider::new(&mut quiver.spacing, 2..=20).text("Spacing"));
+        ui.add(egui::Slider::new(&mut quiver.scale, 0.1..=10.0).logarithmic(true).text("Scale"));
+    }
+}
+
+//one arrow per `spacing` cells, starting half a spacing in so the grid is centered
+
+pub fn draw_quiver(painter: &egui::Painter, fluid: &Fluid, quiver: &QuiverSettings, rect: egui::Rect, cell_w: f32, cell_h: f32) {
+
+    if !quiver.enabled {
+        return;
+    }
+
+    let stroke = egui::Stroke::new(1.0, quiver.color);
+    let spacing = quiver.spacing.max(1);
+
+    for j in (spacing / 2..fluid.height).step_by(spacing) {
+        for i in (spacing / 2..fluid.width).step_by(spacing) {
+            let (u, v) = (fluid.px[(i, j)], fluid.py[(i, j)]);
+            let tail = grid_to_screen(rect, cell_w, cell_h, i as f32, j as f32);
+            let shaft = egui::vec2(u * cell_w, v * cell_h) * quiver.scale;
+
+            //still cells get a dot so the sample grid stays visible
+
+            if shaft.length() < 1.0 {
+                painter.circle_filled(tail, 0.75, quiver.color);
+                continue;
+            }
+
+            let tip = tail + shaft;
+            let back = -shaft.normalized() * shaft.length().min(6.0) * 0.5;
+            let side = back.rot90() * 0.6;
+            painter.line_segment([tail, tip], stroke);
+            painter.line_segment([tip, tip + back + side], stroke);
+            painter.line_segment([tip, tip + back - side], stroke);
+        }
+    }
+}
+
+pub fn flow_controls(ui: &mut egui::Ui, flow: &mut FlowSettings, traces: &mut FlowTraces, fluid: &Fluid) {
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut flow.streamlines, "Streamlines");
+        ui.checkbox(&mut flow.pathlines, "Pathlines");
+        ui.checkbox(&mut flow.streaklines, "Streaklines");
+    });
+    if !(flow.streamlines || flow.pathlines || flow.streaklines) {
+        return;
+    }
+    ui.label(format!("{} seeds, add more with the Seeds tool", traces.seeds().len()));
+    ui.horizontal(|ui| {
+        ui.add(egui::DragValue::new(&mut flow.grid_spacing).clamp_range(2..=50).prefix("spacing "));
+        if ui.button("Seed Grid").clicked() {
+            traces.seed_grid(fluid.width, fluid.height, flow.grid_spacing);
+        }
+        if ui.button("Clear Seeds").clicked() {
+            traces.set_seeds(Vec::new());
+        }
+        if ui.button("Restart Traces").clicked() {
+            traces.restart();
+        }
+    });
+    ui.add(egui::Slider::new(&mut flow.length, 5.0..=300.0).text("Streamline Length"));
+    ui.add(egui::Slider::new(&mut traces.max_points, 10..=1000).text("Trace Points"));
+}
+
+pub fn draw_flow_lines(painter: &egui::Painter, fluid: &Fluid, traces: &FlowTraces, flow: &FlowSettings, rect: egui::Rect, cell_w: f32, cell_h: f32) {
+
+    let to_screen = |&(x, y): &(f32, f32)| grid_to_screen(rect, cell_w, cell_h, x, y);
+    let enabled = flow.streamlines || flow.pathlines || flow.streaklines;
+
+    if flow.streamlines {
+        let steps = (flow.length / STREAMLINE_STEP) as usize;
+        for &seed in traces.seeds() {
+
+            //upstream half reversed so the polyline runs with the flow
+
+            let mut line = fluid.streamline(seed, -STREAMLINE_STEP, steps);
+            line.reverse();
+            line.extend(fluid.streamline(seed, STREAMLINE_STEP, steps).into_iter().skip(1));
+            painter.add(egui::Shape::line(line.iter().map(to_screen).collect(), egui::Stroke::new(1.0, STREAMLINE_COLOR)));
+        }
+    }
+    if flow.pathlines {
+        for path in traces.pathlines() {
+            painter.add(egui::Shape::line(path.iter().map(to_screen).collect(), egui::Stroke::new(1.5, PATHLINE_COLOR)));
+        }
+    }
+    if flow.streaklines {
+        for streak in traces.streaklines() {
+            painter.add(egui::Shape::line(streak.iter().map(to_screen).collect(), egui::Stroke::new(1.5, STREAKLINE_COLOR)));
+        }
+    }
+    if enabled {
+        for seed in traces.seeds() {
+            painter.circle_filled(to_screen(seed), 2.0, egui::Color32::WHITE);
+        }
+    }
+}
+
+//drawing style plus the simulation side settings of the fluid's particle system
+
+pub fn particle_controls(ui: &mut egui::Ui, settings: &mut ParticleSettings, particles: &mut ParticleSystem) {
+    ui.horizontal(|ui| {
+        ui.label(format!("Particles ({})", particles.len()));
+        ui.selectable_value(&mut settings.style, ParticleStyle::Hidden, "Hidden");
+        ui.selectable_value(&mut settings.style, ParticleStyle::Points, "Points");
+        ui.selectable_value(&mut settings.style, ParticleStyle::Trails, "Trails");
+    });
+    if settings.style == ParticleStyle::Hidden {
+        return;
+    }
+    ui.horizontal(|ui| {
+        ui.label("Tracer Integrator");
+        ui.selectable_value(&mut particles.integrator, Integrator::Rk2, "RK2");
+        ui.selectable_value(&mut particles.integrator, Integrator::Rk4, "RK4");
+    });
+
+    //only new particles pick these up, so tracers and heavy particles can share the fluid
+
+    let mut inertial = particles.inertia.is_some();
+    if ui.checkbox(&mut inertial, "Release Inertial").on_hover_text("New particles get mass, drag and gravity").changed() {
+        particles.inertia = inertial.then(Inertia::default);
+    }
+    if let Some(inertia) = &mut particles.inertia {
+        ui.add(egui::Slider::new(&mut inertia.mass, 0.01..=10.0).logarithmic(true).text("Mass"));
+        ui.add(egui::Slider::new(&mut inertia.size, 0.1..=3.0).text("Size (cells)"));
+        ui.add(egui::Slider::new(&mut inertia.drag, 0.0..=20.0).text("Drag"));
+        ui.label(format!("Response time {:.2} s", inertia.response_time()));
+        ui.add(egui::Slider::new(&mut inertia.gravity.0, -10.0..=10.0).text("Gravity X"));
+        ui.add(egui::Slider::new(&mut inertia.gravity.1, -10.0..=10.0).text("Gravity Y"));
+        ui.add(egui::Slider::new(&mut inertia.restitution, 0.0..=1.0).text("Bounce"));
+        ui.checkbox(&mut inertia.two_way, "Push Fluid (two-way)");
+    }
+    ui.horizontal(|ui| {
+        ui.label("Color From");
+        ui.selectable_value(&mut particles.color, ParticleColor::Dye, "Dye");
+        ui.selectable_value(&mut particles.color, ParticleColor::Emitter, "Emitter");
+    });
+    ui.add(egui::Slider::new(&mut particles.lifetime, 1.0..=120.0).text("Lifetime"));
+    ui.add(egui::Slider::new(&mut particles.trail_length, 1..=60).text("Trail Length"));
+    ui.add(egui::Slider::new(&mut settings.size, 0.5..=5.0).text("Size"));
+    if ui.button("Clear Particles").clicked() {
+        particles.clear();
+    }
+}
+
+pub fn draw_particles(painter: &egui::Painter, fluid: &Fluid, settings: &ParticleSettings, rect: egui::Rect, cell_w: f32, cell_h: f32) {
+
+    let to_screen = |&(x, y): &(f32, f32)| grid_to_screen(rect, cell_w, cell_h, x, y);
+
+    for particle in fluid.particles.iter() {
+        let color = egui::Color32::from(particle.color);
+        match settings.style {
+            ParticleStyle::Hidden => return,
+            ParticleStyle::Points => {
+                painter.circle_filled(to_screen(&(particle.x, particle.y)), settings.size, color);
+            }
+            ParticleStyle::Trails => {
+                let trail: Vec<egui::Pos2> = particle.trail.iter().map(to_screen).collect();
+                if trail.len() > 1 {
+                    painter.add(egui::Shape::line(trail, egui::Stroke::new(settings.size, color)));
+                } else {
+                    painter.circle_filled(to_screen(&(particle.x, particle.y)), settings.size, color);
+                }
+            }
+        }
+    }
+}